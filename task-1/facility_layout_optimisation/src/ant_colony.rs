@@ -0,0 +1,417 @@
+use crate::error::{ConfigError, PopulationError};
+use crate::evaluator::Evaluator;
+use crate::facility::{Facility, LayoutContext};
+use crate::facility_layout::FacilityLayout;
+use crate::fitness::Fitness;
+use crate::specimen::{Population, Specimen};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// the probability of a converged colony constructing its best layout again
+const BEST_PROBABILITY: f64 = 0.05;
+
+/// a MAX-MIN ant system, as proposed by Stützle and Hoos for the QAP - every ant assigns the movable
+/// machines to the free cells guided by the pheromone on the machine-cell pairs, only the best ant
+/// of an iteration lays pheromone and the trails are kept within bounds, so that the search
+/// never stops exploring completely
+#[derive(Clone, Debug)]
+pub struct AntColony {
+    pub ants: u32,
+    pub iterations: u32,
+    /// the share of the pheromone which evaporates every iteration
+    pub evaporation: f64,
+    /// improve every ant's layout by swapping pairs of cells before the pheromone is laid
+    pub local_search: bool,
+}
+
+impl AntColony {
+    /// the trail bounds need some pheromone to evaporate, and never more than there is
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(self.evaporation > 0.0 && self.evaporation <= 1.0) {
+            return Err(ConfigError::InvalidEvaporation {
+                evaporation: self.evaporation,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// lets the ants build layouts until the last iteration or the end of the evaluation budget,
+    /// the statistics of the ants of every iteration are appended to the file like by
+    /// Simulation::step, with the best layout found so far as the best one, which is returned
+    pub fn optimise<R: Rng + ?Sized>(
+        &self,
+        evaluator: &Evaluator<Facility>,
+        rng: &mut R,
+        file_name: &str,
+    ) -> Result<Specimen, PopulationError> {
+        let context = evaluator.context;
+        let free_cells = context.dimensions.get_free_cells();
+        let movable_machines = context.dimensions.get_movable_machines();
+
+        // rows are the movable machines, columns the free cells, both by their index
+        // the trails start high, the first update brings them down to the upper bound
+        let mut pheromone: Vec<Vec<f64>> =
+            vec![vec![1.0; free_cells.len()]; movable_machines.len()];
+        let mut best: Option<Specimen> = None;
+
+        for iteration in 0..=self.iterations {
+            let ants: Vec<Specimen> = (0..self.ants)
+                .map(|_x| {
+                    let mut interior = construct(context, &free_cells, &pheromone, rng);
+                    let fitness = self
+                        .local_search
+                        .then(|| local_search(&mut interior, evaluator));
+                    let facility = Facility::new(interior, context.dimensions.width);
+                    let fitness = fitness.unwrap_or_else(|| evaluator.evaluate(&facility));
+
+                    Specimen::new(facility, fitness)
+                })
+                .collect();
+            let ants = Population { specimens: ants };
+
+            let iteration_best = ants.get_best()?.clone();
+            if best
+                .as_ref()
+                .is_none_or(|best| iteration_best.fitness < best.fitness)
+            {
+                best = Some(iteration_best.clone());
+            }
+
+            let best_fitness = best
+                .as_ref()
+                .map_or(iteration_best.fitness, |best| best.fitness);
+            ants.write_statistics_with_best(file_name, best_fitness, evaluator.get_evaluations())?;
+
+            if iteration == self.iterations || evaluator.is_exhausted() {
+                break;
+            }
+
+            let max_pheromone = 1.0 / (self.evaporation * best_fitness.value().max(1.0));
+            // the lower bound of Stützle and Hoos, for n decisions among n / 2 cells on average,
+            // it is only defined for more than 2 decisions and may exceed the upper bound for
+            // a few more, so it never goes above it
+            let decisions = movable_machines.len().max(1) as f64;
+            let min_pheromone = if decisions > 2.0 {
+                let root = BEST_PROBABILITY.powf(1.0 / decisions);
+                max_pheromone * (1.0 - root) / ((decisions / 2.0 - 1.0) * root)
+            } else {
+                max_pheromone / (2.0 * decisions)
+            }
+            .min(max_pheromone);
+
+            for trail in pheromone.iter_mut().flatten() {
+                *trail *= 1.0 - self.evaporation;
+            }
+
+            let deposit = 1.0 / iteration_best.fitness.value().max(1.0);
+            for (machine_index, machine) in movable_machines.iter().enumerate() {
+                if let Some(cell) = iteration_best.genome.cell_of(*machine) {
+                    if let Some(cell_index) = free_cells.iter().position(|free| *free == cell) {
+                        pheromone[machine_index][cell_index] += deposit;
+                    }
+                }
+            }
+
+            for trail in pheromone.iter_mut().flatten() {
+                *trail = trail.clamp(min_pheromone, max_pheromone);
+            }
+        }
+
+        best.ok_or(PopulationError::EmptyPopulation)
+    }
+}
+
+// the machines are placed in a random order, each on one of the cells still free
+// with a probability proportional to the pheromone of the pair
+fn construct<R: Rng + ?Sized>(
+    context: &LayoutContext,
+    free_cells: &[usize],
+    pheromone: &[Vec<f64>],
+    rng: &mut R,
+) -> Vec<Option<u64>> {
+    let dimensions = context.dimensions;
+    let movable_machines = dimensions.get_movable_machines();
+
+    let mut interior: Vec<Option<u64>> =
+        vec![None; (dimensions.width * dimensions.height) as usize];
+    for &(machine, cell) in &dimensions.fixed_machines {
+        interior[cell] = Some(machine);
+    }
+
+    let mut order: Vec<usize> = (0..movable_machines.len()).collect();
+    order.shuffle(rng);
+
+    let mut available: Vec<usize> = (0..free_cells.len()).collect();
+
+    for machine_index in order {
+        let trails = &pheromone[machine_index];
+        let total: f64 = available.iter().map(|&cell_index| trails[cell_index]).sum();
+
+        let mut guess = rng.gen_range(0.0..total);
+        // the last cell is the fallback for rounding errors of the sum
+        let mut chosen = available.len() - 1;
+        for (position, &cell_index) in available.iter().enumerate() {
+            if guess < trails[cell_index] {
+                chosen = position;
+                break;
+            }
+            guess -= trails[cell_index];
+        }
+
+        let cell_index = available.swap_remove(chosen);
+        interior[free_cells[cell_index]] = Some(movable_machines[machine_index]);
+    }
+
+    interior
+}
+
+// first-improvement hill climbing over all swaps of two free cells, until no swap helps anymore,
+// returns the fitness of the improved layout
+// with footprints or a REL chart every swap is a full evaluation, which counts against the
+// budget, and the search stops early once the budget is spent
+fn local_search(interior: &mut [Option<u64>], evaluator: &Evaluator<Facility>) -> Fitness {
+    let context = evaluator.context;
+    let facility_layout = context.facility_layout;
+    let width = context.dimensions.width;
+    let free_cells = context.dimensions.get_free_cells();
+    let evaluate =
+        |interior: &[Option<u64>]| evaluator.evaluate(&Facility::new(interior.to_vec(), width));
+    // a swap may move any of the decoded rectangles or change any adjacency of the REL chart,
+    // so the whole layout is evaluated again, otherwise the swap deltas are enough
+    let mut fitness = (facility_layout.footprints.is_some() || facility_layout.rel_chart.is_some())
+        .then(|| evaluate(interior));
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for (i, &first_cell) in free_cells.iter().enumerate() {
+            for &second_cell in &free_cells[(i + 1)..] {
+                if interior[first_cell].is_none() && interior[second_cell].is_none() {
+                    continue;
+                }
+
+                let (delta, swapped_fitness) = match fitness {
+                    Some(current) if evaluator.is_exhausted() => return current,
+                    Some(current) => {
+                        interior.swap(first_cell, second_cell);
+                        let swapped = evaluate(interior);
+                        interior.swap(first_cell, second_cell);
+                        (swapped.value() - current.value(), Some(swapped))
+                    }
+                    None => (
+                        swap_delta(interior, width, facility_layout, first_cell, second_cell),
+                        None,
+                    ),
+                };
+
+                // the small tolerance keeps rounding errors from swapping back and forth
+                if delta < -1e-9 {
+                    interior.swap(first_cell, second_cell);
+                    fitness = swapped_fitness;
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    fitness.unwrap_or_else(|| evaluate(interior))
+}
+
+// the change of the flow cost if the contents of the two cells were swapped,
+// only the flows of the two machines involved are evaluated, the footprints are ignored
+fn swap_delta(
+    interior: &[Option<u64>],
+    width: u64,
+    facility_layout: &FacilityLayout,
+    first_cell: usize,
+    second_cell: usize,
+) -> f64 {
+    let moved = [interior[first_cell], interior[second_cell]];
+    let cell_of = |machine: u64| interior.iter().position(|&cell| cell == Some(machine));
+    let swapped = |cell: usize| match cell {
+        cell if cell == first_cell => second_cell,
+        cell if cell == second_cell => first_cell,
+        cell => cell,
+    };
+
+    facility_layout
+        .facility_flows
+        .iter()
+        .filter(|flow| moved.contains(&Some(flow.source)) || moved.contains(&Some(flow.dest)))
+        .filter_map(|flow| {
+            let source = cell_of(flow.source)?;
+            let dest = cell_of(flow.dest)?;
+            let model = &facility_layout.distance_model;
+
+            let before = model.distance(width, source, dest);
+            let after = model.distance(width, swapped(source), swapped(dest));
+
+            Some((after - before) * flow.amount * flow.cost)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceModel;
+    use crate::facility_configuration::Dimensions;
+    use crate::facility_layout::FacilityFlow;
+    use crate::rel_chart::{ClosenessRating, Rating, RelChart};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::fs;
+
+    // the trail bounds of Stützle and Hoos cross for up to 4 machines
+    #[test]
+    fn optimises_instances_with_few_machines() {
+        for machines in 1..=4 {
+            let dimensions = Dimensions {
+                width: 2,
+                height: 2,
+                machines: (0..machines).collect(),
+                fixed_machines: Vec::new(),
+                blocked_cells: Vec::new(),
+            };
+            let facility_layout = FacilityLayout {
+                facility_flows: (1..machines)
+                    .map(|dest| FacilityFlow {
+                        source: 0,
+                        dest,
+                        amount: 2.0,
+                        cost: 1.5,
+                    })
+                    .collect(),
+                distance_model: DistanceModel::Manhattan,
+                footprints: None,
+                rel_chart: None,
+            };
+            let context = LayoutContext::new(&dimensions, &facility_layout);
+
+            let file_name = std::env::temp_dir().join(format!(
+                "ant_colony_{}_{}.txt",
+                std::process::id(),
+                machines
+            ));
+            let file_name = file_name.to_str().unwrap();
+            fs::write(file_name, "").unwrap();
+
+            let best = AntColony {
+                ants: 5,
+                iterations: 3,
+                evaporation: 0.2,
+                local_search: false,
+            }
+            .optimise(
+                &Evaluator::new(&context, None),
+                &mut ChaCha8Rng::seed_from_u64(1),
+                file_name,
+            )
+            .unwrap();
+            let statistics = fs::read_to_string(file_name).unwrap();
+            fs::remove_file(file_name).unwrap();
+
+            // the last row holds the best layout of all the iterations
+            let last_best = statistics
+                .lines()
+                .last()
+                .unwrap()
+                .split(',')
+                .next()
+                .unwrap();
+            assert_eq!(last_best, best.fitness.to_string());
+
+            for machine in 0..machines {
+                assert!(best.genome.cell_of(machine).is_some());
+            }
+            // two machines can be next to machine 0, a third one is diagonal to it
+            let optimum = [0.0, 3.0, 6.0, 12.0][machines as usize - 1];
+            assert_eq!(best.fitness.value(), optimum);
+        }
+    }
+
+    #[test]
+    fn rejects_evaporation_outside_of_the_unit_interval() {
+        let ant_colony = |evaporation| AntColony {
+            ants: 5,
+            iterations: 3,
+            evaporation,
+            local_search: false,
+        };
+
+        for evaporation in [0.0, -0.5, 1.5, f64::NAN] {
+            assert!(matches!(
+                ant_colony(evaporation).validate(),
+                Err(ConfigError::InvalidEvaporation { .. })
+            ));
+        }
+        for evaporation in [0.2, 1.0] {
+            assert!(ant_colony(evaporation).validate().is_ok());
+        }
+    }
+
+    // with a REL chart every swap is a full evaluation, without one only the final layout is
+    #[test]
+    fn counts_the_full_evaluations_of_the_local_search() {
+        let dimensions = Dimensions {
+            width: 3,
+            height: 3,
+            machines: (0..6).collect(),
+            fixed_machines: Vec::new(),
+            blocked_cells: Vec::new(),
+        };
+        let mut facility_layout = FacilityLayout {
+            facility_flows: (1..6)
+                .map(|dest| FacilityFlow {
+                    source: 0,
+                    dest,
+                    amount: 1.0,
+                    cost: 1.0,
+                })
+                .collect(),
+            distance_model: DistanceModel::Manhattan,
+            footprints: None,
+            rel_chart: None,
+        };
+        let interior = vec![
+            Some(0),
+            None,
+            Some(1),
+            None,
+            Some(2),
+            Some(3),
+            Some(4),
+            Some(5),
+            None,
+        ];
+        let facility = Facility::new(interior.clone(), 3);
+
+        let context = LayoutContext::new(&dimensions, &facility_layout);
+        let evaluator = Evaluator::new(&context, None);
+        let fitness = local_search(&mut interior.clone(), &evaluator);
+        assert_eq!(evaluator.get_evaluations(), 1);
+        assert!(fitness < facility.calculate_fitness(&facility_layout));
+
+        facility_layout.rel_chart = Some(RelChart {
+            ratings: vec![ClosenessRating {
+                first: 1,
+                second: 2,
+                rating: Rating::A,
+            }],
+            flow_weight: 1.0,
+            closeness_weight: 1.0,
+        });
+        let context = LayoutContext::new(&dimensions, &facility_layout);
+        let evaluator = Evaluator::new(&context, Some(5));
+        let mut searched = interior.clone();
+        let fitness = local_search(&mut searched, &evaluator);
+        assert_eq!(evaluator.get_evaluations(), 5);
+        assert_eq!(
+            fitness,
+            Facility::new(searched, 3).calculate_fitness(&facility_layout)
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::error::ParseError;
+use crate::facility::Facility;
+use crate::fitness::Fitness;
+use crate::genome::LayoutGenome;
+use crate::specimen::{Population, Selection, Simulation, SimulationParameters, Specimen};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde_json::{json, Value};
+
+use std::fs;
+use std::io;
+
+/// everything an experiment needs to continue exactly where it stopped - the arguments it was started
+/// with, the run and the state of its genetic algorithm, the best specimen of the finished runs
+/// and the random number generator all the runs share
+/// the simulation evolves any encoding of the layouts, the best specimen is always decoded
+pub struct ExperimentState<G = Facility> {
+    pub args: Vec<String>,
+    pub run: u32,
+    pub simulation: Option<Simulation<G>>,
+    /// the fitness evaluations of the current run so far
+    pub evaluations: u64,
+    pub best: Option<Specimen>,
+    pub rng: ChaCha8Rng,
+}
+
+impl<G: LayoutGenome> ExperimentState<G> {
+    /// the state of a new experiment, before its first run
+    pub fn new(args: Vec<String>, rng: ChaCha8Rng) -> Self {
+        ExperimentState {
+            args,
+            run: 0,
+            simulation: None,
+            evaluations: 0,
+            best: None,
+            rng,
+        }
+    }
+
+    /// the statistics are the contents of the results file so far, they are restored on resume
+    pub fn write_checkpoint(&self, path: &str, statistics: &str) -> io::Result<()> {
+        let checkpoint = json!({
+            "args": self.args,
+            "run": self.run,
+            "simulation": self.simulation.as_ref().map(simulation_to_json),
+            "evaluations": self.evaluations,
+            "best": self.best.as_ref().map(specimen_to_json),
+            "rng": {
+                "seed": self.rng.get_seed(),
+                "stream": self.rng.get_stream(),
+                // JSON numbers cannot hold all 128 bits
+                "word_pos": self.rng.get_word_pos().to_string(),
+            },
+            "statistics": statistics,
+        });
+
+        // the checkpoint is replaced at once, so that an interruption never leaves half of one behind
+        let temporary_path = format!("{}.tmp", path);
+        fs::write(&temporary_path, serde_json::to_string(&checkpoint)?)?;
+        fs::rename(&temporary_path, path)
+    }
+}
+
+/// the arguments the experiment of the checkpoint was started with, they tell its encoding
+pub fn read_checkpoint_args(path: &str) -> Result<Vec<String>, ParseError> {
+    parse_args(&read_value(path)?, path)
+}
+
+/// returns the state together with the statistics written so far
+pub fn read_checkpoint<G: LayoutGenome>(
+    path: &str,
+) -> Result<(ExperimentState<G>, String), ParseError> {
+    let value = read_value(path)?;
+    let malformed = |reason: &str| ParseError::Malformed {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let args = parse_args(&value, path)?;
+    let run = value["run"]
+        .as_u64()
+        .ok_or_else(|| malformed("invalid run"))? as u32;
+    let evaluations = value["evaluations"]
+        .as_u64()
+        .ok_or_else(|| malformed("invalid evaluations"))?;
+    let statistics = value["statistics"]
+        .as_str()
+        .ok_or_else(|| malformed("invalid statistics"))?
+        .to_string();
+
+    let simulation = match &value["simulation"] {
+        Value::Null => None,
+        simulation => {
+            Some(simulation_from_json(simulation).ok_or_else(|| malformed("invalid simulation"))?)
+        }
+    };
+    let best = match &value["best"] {
+        Value::Null => None,
+        best => Some(
+            specimen_from_json::<Facility>(best)
+                .ok_or_else(|| malformed("invalid best specimen"))?,
+        ),
+    };
+    let rng =
+        rng_from_json(&value["rng"]).ok_or_else(|| malformed("invalid random number generator"))?;
+
+    Ok((
+        ExperimentState {
+            args,
+            run,
+            simulation,
+            evaluations,
+            best,
+            rng,
+        },
+        statistics,
+    ))
+}
+
+fn read_value(path: &str) -> Result<Value, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+
+    serde_json::from_str(&contents).map_err(|source| ParseError::Json {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn parse_args(value: &Value, path: &str) -> Result<Vec<String>, ParseError> {
+    value["args"]
+        .as_array()
+        .and_then(|args| {
+            args.iter()
+                .map(|arg| arg.as_str().map(String::from))
+                .collect::<Option<Vec<String>>>()
+        })
+        .ok_or_else(|| ParseError::Malformed {
+            path: path.to_string(),
+            reason: String::from("invalid arguments"),
+        })
+}
+
+fn simulation_to_json<G: LayoutGenome>(simulation: &Simulation<G>) -> Value {
+    let parameters = &simulation.parameters;
+    let selection = match parameters.selection {
+        Selection::Tournament(tournament_size) => format!("tournament:{}", tournament_size),
+        Selection::Roulette => String::from("roulette"),
+        Selection::Random => String::from("random"),
+    };
+
+    json!({
+        "population_size": parameters.population_size,
+        "generations": parameters.generations,
+        "selection": selection,
+        "crossover_factor": parameters.crossover_factor,
+        "mutation_factor": parameters.mutation_factor,
+        "generation": simulation.generation,
+        "specimens": simulation
+            .population
+            .specimens
+            .iter()
+            .map(specimen_to_json)
+            .collect::<Vec<Value>>(),
+    })
+}
+
+fn simulation_from_json<G: LayoutGenome>(value: &Value) -> Option<Simulation<G>> {
+    let selection = match value["selection"].as_str()? {
+        "roulette" => Selection::Roulette,
+        "random" => Selection::Random,
+        selection => Selection::Tournament(selection.strip_prefix("tournament:")?.parse().ok()?),
+    };
+
+    let parameters = SimulationParameters {
+        population_size: value["population_size"].as_u64()? as u32,
+        generations: value["generations"].as_u64()? as u32,
+        selection,
+        crossover_factor: value["crossover_factor"].as_f64()?,
+        mutation_factor: value["mutation_factor"].as_f64()?,
+    };
+    let specimens = value["specimens"]
+        .as_array()?
+        .iter()
+        .map(specimen_from_json)
+        .collect::<Option<Vec<Specimen<G>>>>()?;
+
+    Some(Simulation::resume(
+        parameters,
+        Population { specimens },
+        value["generation"].as_u64()? as u32,
+    ))
+}
+
+fn specimen_to_json<G: LayoutGenome>(specimen: &Specimen<G>) -> Value {
+    json!({
+        "genome": specimen.genome.to_value(),
+        "fitness": fitness_to_json(specimen.fitness),
+    })
+}
+
+fn specimen_from_json<G: LayoutGenome>(value: &Value) -> Option<Specimen<G>> {
+    Some(Specimen::new(
+        G::from_value(&value["genome"])?,
+        fitness_from_json(&value["fitness"])?,
+    ))
+}
+
+// JSON numbers cannot be infinite or NaN, so those are written as strings like "inf"
+fn fitness_to_json(fitness: Fitness) -> Value {
+    match fitness.value() {
+        value if value.is_finite() => json!(value),
+        value => json!(value.to_string()),
+    }
+}
+
+fn fitness_from_json(value: &Value) -> Option<Fitness> {
+    match value {
+        Value::String(value) => value.parse().ok().map(Fitness::new),
+        value => value.as_f64().map(Fitness::new),
+    }
+}
+
+fn rng_from_json(value: &Value) -> Option<ChaCha8Rng> {
+    let seed: Vec<u8> = value["seed"]
+        .as_array()?
+        .iter()
+        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+        .collect::<Option<Vec<u8>>>()?;
+
+    let mut rng = ChaCha8Rng::from_seed(seed.try_into().ok()?);
+    rng.set_stream(value["stream"].as_u64()?);
+    rng.set_word_pos(value["word_pos"].as_str()?.parse().ok()?);
+
+    Some(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    // a resumed run has to continue with exactly the fitnesses it stopped with
+    #[test]
+    fn restores_fractional_fitnesses_exactly() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let genome = Facility::new(vec![Some(0), None, Some(1), Some(2)], 2);
+
+        for _ in 0..10_000 {
+            let fitness = rng.gen::<f64>() * 10f64.powi(rng.gen_range(-3..9));
+            let specimen = Specimen::new(genome.clone(), Fitness::new(fitness));
+
+            let json = serde_json::to_string(&specimen_to_json(&specimen)).unwrap();
+            let restored: Specimen =
+                specimen_from_json(&serde_json::from_str(&json).unwrap()).unwrap();
+
+            assert_eq!(restored.fitness.value().to_bits(), fitness.to_bits());
+            assert_eq!(restored.genome, genome);
+        }
+    }
+
+    #[test]
+    fn restores_non_finite_fitnesses() {
+        let genome = Facility::new(vec![Some(0), None, Some(1), Some(2)], 2);
+
+        for fitness in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+            let specimen = Specimen::new(genome.clone(), Fitness::new(fitness));
+
+            let json = serde_json::to_string(&specimen_to_json(&specimen)).unwrap();
+            let restored: Specimen =
+                specimen_from_json(&serde_json::from_str(&json).unwrap()).unwrap();
+
+            assert_eq!(restored.fitness, Fitness::new(fitness));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use crate::run_log::{GenerationRecord, RunLog};
+use std::fmt::Write;
+
+const WIDTH: f64 = 900.0;
+const HEIGHT: f64 = 540.0;
+const MARGIN_LEFT: f64 = 90.0;
+const MARGIN_RIGHT: f64 = 150.0;
+const MARGIN_TOP: f64 = 50.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+
+const BEST_COLOUR: &str = "#2ca02c";
+const AVERAGE_COLOUR: &str = "#1f77b4";
+const WORST_COLOUR: &str = "#d62728";
+
+pub enum RunAggregation {
+    /// every run is drawn on top of the others with reduced opacity
+    Overlay,
+    /// the runs are averaged generation by generation into a single series
+    Average,
+}
+
+pub fn render_convergence_svg(run_log: &RunLog, aggregation: &RunAggregation) -> String {
+    let series: Vec<Vec<GenerationRecord>> = match aggregation {
+        RunAggregation::Overlay => run_log.runs.clone(),
+        RunAggregation::Average => vec![average_runs(&run_log.runs)],
+    };
+    let opacity = match aggregation {
+        RunAggregation::Overlay if series.len() > 1 => 0.35,
+        _ => 1.0,
+    };
+
+    let generations = series.iter().map(Vec::len).max().unwrap_or(0).max(2);
+    let (min_fitness, max_fitness) = fitness_bounds(&series);
+    let y_ticks = nice_ticks(min_fitness, max_fitness, 6);
+    let y_min = *y_ticks.first().unwrap_or(&min_fitness);
+    let y_max = *y_ticks.last().unwrap_or(&max_fitness);
+
+    let plot = PlotArea {
+        x_max: (generations - 1) as f64,
+        y_min,
+        y_max: if y_max > y_min { y_max } else { y_min + 1.0 },
+    };
+
+    let mut svg = String::new();
+    // writing to a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        WIDTH, HEIGHT
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle" font-size="16">{}</text>"#,
+        MARGIN_LEFT + plot.width() / 2.0,
+        MARGIN_TOP / 2.0 + 6.0,
+        escape(&format!("{} ({} runs)", run_log.name, run_log.runs.len()))
+    );
+
+    plot.write_axes(&mut svg, &nice_ticks(0.0, plot.x_max, 10), &y_ticks);
+
+    for run in &series {
+        let band: Vec<(f64, f64, f64)> = run
+            .iter()
+            .enumerate()
+            .map(|(generation, record)| {
+                (
+                    generation as f64,
+                    record.average - record.deviation,
+                    record.average + record.deviation,
+                )
+            })
+            .collect();
+        plot.write_band(&mut svg, &band, AVERAGE_COLOUR, 0.15 * opacity);
+    }
+
+    for run in &series {
+        plot.write_line(&mut svg, run, |record| record.worst, WORST_COLOUR, opacity);
+        plot.write_line(
+            &mut svg,
+            run,
+            |record| record.average,
+            AVERAGE_COLOUR,
+            opacity,
+        );
+        plot.write_line(&mut svg, run, |record| record.best, BEST_COLOUR, opacity);
+    }
+
+    write_legend(&mut svg);
+    let _ = writeln!(svg, "</svg>");
+
+    svg
+}
+
+// averages every statistic over the runs which reached a given generation
+fn average_runs(runs: &[Vec<GenerationRecord>]) -> Vec<GenerationRecord> {
+    let generations = runs.iter().map(Vec::len).max().unwrap_or(0);
+
+    (0..generations)
+        .map(|generation| {
+            let records: Vec<&GenerationRecord> =
+                runs.iter().filter_map(|run| run.get(generation)).collect();
+            let count = records.len() as f64;
+
+            GenerationRecord {
+                best: records.iter().map(|record| record.best).sum::<f64>() / count,
+                worst: records.iter().map(|record| record.worst).sum::<f64>() / count,
+                average: records.iter().map(|record| record.average).sum::<f64>() / count,
+                deviation: records.iter().map(|record| record.deviation).sum::<f64>() / count,
+            }
+        })
+        .collect()
+}
+
+fn fitness_bounds(series: &[Vec<GenerationRecord>]) -> (f64, f64) {
+    series
+        .iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), record| {
+            (
+                min.min(record.best).min(record.average - record.deviation),
+                max.max(record.worst).max(record.average + record.deviation),
+            )
+        })
+}
+
+// evenly spaced "round" tick values (1, 2 or 5 times a power of ten) covering [min, max]
+fn nice_ticks(min: f64, max: f64, target_count: usize) -> Vec<f64> {
+    if !min.is_finite() || !max.is_finite() {
+        return vec![0.0, 1.0];
+    }
+    if max <= min {
+        return vec![min, min + 1.0];
+    }
+
+    let raw_step = (max - min) / target_count as f64;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw_step)
+        .unwrap_or(10.0 * magnitude);
+
+    let first = (min / step).floor() as i64;
+    let last = (max / step).ceil() as i64;
+
+    (first..=last).map(|tick| tick as f64 * step).collect()
+}
+
+struct PlotArea {
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+}
+
+impl PlotArea {
+    fn width(&self) -> f64 {
+        WIDTH - MARGIN_LEFT - MARGIN_RIGHT
+    }
+
+    fn height(&self) -> f64 {
+        HEIGHT - MARGIN_TOP - MARGIN_BOTTOM
+    }
+
+    fn x(&self, generation: f64) -> f64 {
+        MARGIN_LEFT + generation / self.x_max * self.width()
+    }
+
+    fn y(&self, fitness: f64) -> f64 {
+        MARGIN_TOP + (self.y_max - fitness) / (self.y_max - self.y_min) * self.height()
+    }
+
+    fn write_axes(&self, svg: &mut String, x_ticks: &[f64], y_ticks: &[f64]) {
+        let bottom = MARGIN_TOP + self.height();
+        let right = MARGIN_LEFT + self.width();
+
+        for &tick in y_ticks {
+            let y = self.y(tick);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#e0e0e0"/>"##,
+                MARGIN_LEFT, y, right, y
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+                MARGIN_LEFT - 6.0,
+                y + 4.0,
+                format_tick(tick)
+            );
+        }
+
+        // generations are whole numbers, so fractional ticks are skipped
+        for &tick in x_ticks
+            .iter()
+            .filter(|tick| **tick <= self.x_max && tick.fract() == 0.0)
+        {
+            let x = self.x(tick);
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="black"/>"#,
+                x,
+                bottom,
+                x,
+                bottom + 5.0
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                x,
+                bottom + 18.0,
+                format_tick(tick)
+            );
+        }
+
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="none" stroke="black"/>"#,
+            MARGIN_LEFT,
+            MARGIN_TOP,
+            self.width(),
+            self.height()
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">generation</text>"#,
+            MARGIN_LEFT + self.width() / 2.0,
+            HEIGHT - 15.0
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text transform="translate(20 {:.1}) rotate(-90)" text-anchor="middle">fitness</text>"#,
+            MARGIN_TOP + self.height() / 2.0
+        );
+    }
+
+    // the points are (generation, lower, upper)
+    fn write_band(&self, svg: &mut String, band: &[(f64, f64, f64)], colour: &str, opacity: f64) {
+        if band.is_empty() {
+            return;
+        }
+
+        let upper = band.iter().map(|(generation, _, upper)| {
+            format!("{:.1},{:.1}", self.x(*generation), self.y(*upper))
+        });
+        let lower = band.iter().rev().map(|(generation, lower, _)| {
+            format!("{:.1},{:.1}", self.x(*generation), self.y(*lower))
+        });
+
+        let _ = writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{}" fill-opacity="{:.2}" stroke="none"/>"#,
+            upper.chain(lower).collect::<Vec<String>>().join(" "),
+            colour,
+            opacity
+        );
+    }
+
+    fn write_line<F>(
+        &self,
+        svg: &mut String,
+        run: &[GenerationRecord],
+        value: F,
+        colour: &str,
+        opacity: f64,
+    ) where
+        F: Fn(&GenerationRecord) -> f64,
+    {
+        let points: Vec<String> = run
+            .iter()
+            .enumerate()
+            .map(|(generation, record)| {
+                format!(
+                    "{:.1},{:.1}",
+                    self.x(generation as f64),
+                    self.y(value(record))
+                )
+            })
+            .collect();
+
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-opacity="{:.2}" stroke-width="1.5"/>"#,
+            points.join(" "),
+            colour,
+            opacity
+        );
+    }
+}
+
+fn write_legend(svg: &mut String) {
+    let x = WIDTH - MARGIN_RIGHT + 15.0;
+    let entries = [
+        ("best", BEST_COLOUR),
+        ("average", AVERAGE_COLOUR),
+        ("worst", WORST_COLOUR),
+    ];
+
+    for (i, (label, colour)) in entries.iter().enumerate() {
+        let y = MARGIN_TOP + 10.0 + i as f64 * 20.0;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="2"/>"#,
+            x,
+            y,
+            x + 20.0,
+            y,
+            colour
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+            x + 26.0,
+            y + 4.0,
+            label
+        );
+    }
+
+    let y = MARGIN_TOP + 10.0 + entries.len() as f64 * 20.0;
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{:.1}" y="{:.1}" width="20" height="10" fill="{}" fill-opacity="0.15"/>"#,
+        x,
+        y - 5.0,
+        AVERAGE_COLOUR
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}">average ± std dev</text>"#,
+        x + 26.0,
+        y + 4.0
+    );
+}
+
+// removes the floating point noise from multiples of the tick step
+fn format_tick(tick: f64) -> String {
+    format!("{}", (tick * 1e6).round() / 1e6)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,127 @@
+use crate::error::PopulationError;
+use crate::evaluator::Evaluator;
+use crate::random_key::RandomKeys;
+use crate::specimen::{Population, Specimen};
+
+use rand::seq::index::sample;
+use rand::Rng;
+
+/// which vector is perturbed by the scaled difference of two others, the trial vectors always
+/// take their keys from the mutant by binomial crossover
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// DE/rand/1/bin - a random vector, explores more
+    Rand1Bin,
+    /// DE/best/1/bin - the best vector of the generation, converges faster
+    Best1Bin,
+}
+
+#[derive(Clone, Debug)]
+pub struct DifferentialEvolution {
+    pub population_size: u32,
+    pub generations: u32,
+    pub strategy: Strategy,
+    /// F, the scale of the difference vector
+    pub differential_weight: f64,
+    /// CR, the probability of a key coming from the mutant
+    pub crossover_rate: f64,
+}
+
+impl DifferentialEvolution {
+    /// evolves random-key layouts until the last generation or the end of the evaluation budget,
+    /// the statistics of every generation are appended to the file like by Simulation::step
+    /// and the best specimen of the last generation is returned
+    pub fn optimise<R: Rng + ?Sized>(
+        &self,
+        evaluator: &Evaluator<RandomKeys>,
+        rng: &mut R,
+        file_name: &str,
+    ) -> Result<Specimen<RandomKeys>, PopulationError> {
+        // the target and three other distinct vectors
+        if self.population_size < 4 {
+            return Err(PopulationError::PopulationTooSmall {
+                population_size: self.population_size as usize,
+                required: 4,
+            });
+        }
+
+        let mut population: Population<RandomKeys> =
+            Population::random(self.population_size, evaluator, rng);
+
+        for generation in 0..=self.generations {
+            population.write_statistics(file_name, evaluator.get_evaluations())?;
+
+            if generation == self.generations || evaluator.is_exhausted() {
+                break;
+            }
+
+            let best = population.get_best()?.genome.clone();
+            let specimens = &population.specimens;
+
+            let next_generation: Vec<Specimen<RandomKeys>> = specimens
+                .iter()
+                .enumerate()
+                .map(|(target_index, target)| {
+                    let trial = self.trial_vector(target_index, specimens, &best, rng);
+                    let fitness = evaluator.evaluate(&trial);
+
+                    // the trial replaces its target unless it is worse, so that the search
+                    // can move across plateaus of equal fitness
+                    if fitness <= target.fitness {
+                        Specimen::new(trial, fitness)
+                    } else {
+                        target.clone()
+                    }
+                })
+                .collect();
+
+            population = Population {
+                specimens: next_generation,
+            };
+        }
+
+        population.get_best().cloned()
+    }
+
+    fn trial_vector<R: Rng + ?Sized>(
+        &self,
+        target_index: usize,
+        specimens: &[Specimen<RandomKeys>],
+        best: &RandomKeys,
+        rng: &mut R,
+    ) -> RandomKeys {
+        // three distinct vectors which are not the target
+        let others: Vec<&[f64]> = sample(rng, specimens.len() - 1, 3)
+            .into_iter()
+            .map(|index| {
+                if index >= target_index {
+                    index + 1
+                } else {
+                    index
+                }
+            })
+            .map(|index| specimens[index].genome.get_keys())
+            .collect();
+
+        let base = match self.strategy {
+            Strategy::Rand1Bin => others[0],
+            Strategy::Best1Bin => best.get_keys(),
+        };
+
+        let target = specimens[target_index].genome.get_keys();
+        // at least this key comes from the mutant, so that the trial differs from the target
+        let forced_key = rng.gen_range(0..target.len().max(1));
+
+        let keys = (0..target.len())
+            .map(|key| {
+                if key == forced_key || rng.gen_bool(self.crossover_rate) {
+                    base[key] + self.differential_weight * (others[1][key] - others[2][key])
+                } else {
+                    target[key]
+                }
+            })
+            .collect();
+
+        RandomKeys::new(keys)
+    }
+}
@@ -0,0 +1,103 @@
+/// how the distance between two cells of the facility is measured
+/// the cells are numbered row by row, the grid metrics work on their (row, column) positions
+#[derive(Clone, Debug)]
+pub enum DistanceModel {
+    Manhattan,
+    Euclidean,
+    Chebyshev,
+    Matrix(DistanceMatrix),
+}
+
+impl DistanceModel {
+    pub fn distance(&self, width: u64, from_cell: usize, to_cell: usize) -> f64 {
+        let (from_row, from_column) = (from_cell as u64 / width, from_cell as u64 % width);
+        let (to_row, to_column) = (to_cell as u64 / width, to_cell as u64 % width);
+        let rows = from_row.abs_diff(to_row) as f64;
+        let columns = from_column.abs_diff(to_column) as f64;
+
+        match self {
+            DistanceModel::Manhattan => rows + columns,
+            DistanceModel::Euclidean => rows.hypot(columns),
+            DistanceModel::Chebyshev => rows.max(columns),
+            DistanceModel::Matrix(distance_matrix) => distance_matrix.get(from_cell, to_cell),
+        }
+    }
+
+    /// the distance between two (row, column) points inside of cells, e.g. the centroids of machines
+    /// which occupy several cells, the matrix falls back to the cells containing the points
+    pub fn point_distance(&self, width: u64, from: (f64, f64), to: (f64, f64)) -> f64 {
+        let rows = (from.0 - to.0).abs();
+        let columns = (from.1 - to.1).abs();
+
+        match self {
+            DistanceModel::Manhattan => rows + columns,
+            DistanceModel::Euclidean => rows.hypot(columns),
+            DistanceModel::Chebyshev => rows.max(columns),
+            DistanceModel::Matrix(distance_matrix) => {
+                let cell =
+                    |(row, column): (f64, f64)| row as usize * width as usize + column as usize;
+                distance_matrix.get(cell(from), cell(to))
+            }
+        }
+    }
+
+    /// the largest distance between any two cells of a width x height grid, no two points inside
+    /// of the cells are further apart
+    pub fn max_distance(&self, width: u64, height: u64) -> f64 {
+        let rows = height.saturating_sub(1) as f64;
+        let columns = width.saturating_sub(1) as f64;
+
+        match self {
+            DistanceModel::Manhattan => rows + columns,
+            DistanceModel::Euclidean => rows.hypot(columns),
+            DistanceModel::Chebyshev => rows.max(columns),
+            DistanceModel::Matrix(distance_matrix) => distance_matrix
+                .distances
+                .iter()
+                .copied()
+                .fold(0.0, f64::max),
+        }
+    }
+
+    /// manhattan, euclidean or chebyshev - matrices are read from files instead
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "manhattan" => Some(DistanceModel::Manhattan),
+            "euclidean" => Some(DistanceModel::Euclidean),
+            "chebyshev" => Some(DistanceModel::Chebyshev),
+            _ => None,
+        }
+    }
+}
+
+/// an explicit cell-to-cell distance table, e.g. the real aisle distances of a factory
+#[derive(Clone, Debug)]
+pub struct DistanceMatrix {
+    size: usize,
+    distances: Vec<f64>,
+}
+
+impl DistanceMatrix {
+    /// the distances are given row by row, so there have to be exactly size * size of them
+    pub fn new(size: usize, distances: Vec<f64>) -> Option<Self> {
+        if distances.len() != size * size {
+            return None;
+        }
+
+        Some(DistanceMatrix { size, distances })
+    }
+
+    /// tabulates the distances of any model for a width x height grid
+    pub fn from_model(distance_model: &DistanceModel, width: u64, height: u64) -> Self {
+        let size = (width * height) as usize;
+        let distances = (0..size)
+            .flat_map(|from| (0..size).map(move |to| distance_model.distance(width, from, to)))
+            .collect();
+
+        DistanceMatrix { size, distances }
+    }
+
+    pub fn get(&self, from_cell: usize, to_cell: usize) -> f64 {
+        self.distances[from_cell * self.size + to_cell]
+    }
+}
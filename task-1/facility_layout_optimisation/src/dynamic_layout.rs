@@ -0,0 +1,357 @@
+use crate::error::{ParseError, PopulationError};
+use crate::evaluator::Evaluator;
+use crate::facility::{generate_randomised_facilities, Facility};
+use crate::facility_layout::FacilityLayout;
+use crate::fitness::Fitness;
+use crate::flow_parser::{parse_amount, parse_field, parse_flows};
+use serde_json::Value;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// the flows of every planning period, on the same grid, together with the cost of moving a machine
+/// to another cell between two consecutive periods
+pub struct DynamicInstance {
+    pub periods: Vec<FacilityLayout>,
+    pub rearrangement_cost: f64,
+    /// the machines whose moves cost something else than rearrangement_cost
+    pub rearrangement_costs: HashMap<u64, f64>,
+}
+
+/// one layout for every period
+#[derive(Clone, Debug)]
+pub struct LayoutPlan {
+    pub layouts: Vec<Facility>,
+    pub flow_cost: Fitness,
+    pub rearrangement_cost: f64,
+}
+
+impl LayoutPlan {
+    fn new(layouts: Vec<Facility>, instance: &DynamicInstance) -> Self {
+        let flow_cost = layouts
+            .iter()
+            .zip(&instance.periods)
+            .map(|(layout, period)| layout.calculate_fitness(period))
+            .sum();
+        let rearrangement_cost = layouts
+            .windows(2)
+            .map(|pair| instance.calculate_rearrangement_cost(&pair[0], &pair[1]))
+            .fold(0.0, |total, cost| total + cost);
+
+        LayoutPlan {
+            layouts,
+            flow_cost,
+            rearrangement_cost,
+        }
+    }
+
+    pub fn get_total_cost(&self) -> Fitness {
+        self.flow_cost + Fitness::new(self.rearrangement_cost)
+    }
+}
+
+impl DynamicInstance {
+    /// every machine which is on another cell in the next period pays its rearrangement cost
+    pub fn calculate_rearrangement_cost(&self, previous: &Facility, next: &Facility) -> f64 {
+        (0..previous.get_height())
+            .flat_map(|row| (0..previous.get_width()).map(move |column| (row, column)))
+            .filter_map(|(row, column)| previous.get_machine(row, column))
+            .filter(|machine| previous.cell_of(*machine) != next.cell_of(*machine))
+            .map(|machine| {
+                *self
+                    .rearrangement_costs
+                    .get(&machine)
+                    .unwrap_or(&self.rearrangement_cost)
+            })
+            // the sum of no costs would be -0
+            .fold(0.0, |total, cost| total + cost)
+    }
+
+    /// a genetic algorithm over whole plans - the crossover and the mutation of the single period
+    /// algorithm work on the layouts of every period, and a plan may copy the layout of one period
+    /// into its neighbour, which is what makes keeping a layout over several periods attractive
+    /// the statistics of the total costs are appended to the file like by Simulation::step
+    /// the evaluator gives the grid, the costs of a whole plan count as a single evaluation
+    #[allow(clippy::too_many_arguments)]
+    pub fn optimise<R: Rng + ?Sized>(
+        &self,
+        population_size: u32,
+        generations: u32,
+        tournament_size: usize,
+        evaluator: &Evaluator<Facility>,
+        rng: &mut R,
+        crossover_factor: f64,
+        mutation_factor: f64,
+        file_name: &str,
+    ) -> Result<LayoutPlan, PopulationError> {
+        let dimensions = evaluator.context.dimensions;
+        let evaluate = |layouts: Vec<Facility>| {
+            evaluator.evaluate_with(|_context| LayoutPlan::new(layouts, self))
+        };
+
+        let mut population: Vec<LayoutPlan> = (0..population_size)
+            .map(|_x| {
+                evaluate(generate_randomised_facilities(
+                    dimensions,
+                    self.periods.len() as u32,
+                    rng,
+                ))
+            })
+            .collect();
+
+        let max_machine = *population
+            .first()
+            .and_then(|plan| plan.layouts.first())
+            .and_then(|layout| layout.find_max_machine())
+            .ok_or(PopulationError::EmptyPopulation)?;
+
+        let mut file = OpenOptions::new().append(true).open(file_name)?;
+
+        for generation in 0..=generations {
+            let stats = calculate_statistics(&population)?;
+            writeln!(
+                file,
+                "{},{},{},{},{}",
+                stats.0,
+                stats.1,
+                stats.2,
+                stats.3,
+                evaluator.get_evaluations()
+            )?;
+
+            if generation == generations || evaluator.is_exhausted() {
+                break;
+            }
+
+            // the best plan always survives
+            let best = best_plan(&population)?.clone();
+            let mut next_population: Vec<LayoutPlan> = vec![best];
+
+            while next_population.len() < population.len() {
+                let first = select_by_tournament(&population, tournament_size, rng)?;
+                let second = select_by_tournament(&population, tournament_size, rng)?;
+
+                let mut layouts: Vec<Facility> = if rng.gen_bool(crossover_factor) {
+                    first
+                        .layouts
+                        .iter()
+                        .zip(&second.layouts)
+                        .map(|(first, second)| first.crossover(second, dimensions, rng).0)
+                        .collect()
+                } else {
+                    first.layouts.clone()
+                };
+
+                if rng.gen_bool(mutation_factor) {
+                    let period = rng.gen_range(0..layouts.len());
+                    layouts[period].mutate(mutation_factor, max_machine, dimensions, rng);
+                }
+
+                if layouts.len() > 1 && rng.gen_bool(mutation_factor) {
+                    let period = rng.gen_range(0..(layouts.len() - 1));
+                    if rng.gen_bool(0.5) {
+                        layouts[period + 1] = layouts[period].clone();
+                    } else {
+                        layouts[period] = layouts[period + 1].clone();
+                    }
+                }
+
+                next_population.push(evaluate(layouts));
+            }
+
+            population = next_population;
+        }
+
+        best_plan(&population).cloned()
+    }
+}
+
+fn best_plan(population: &[LayoutPlan]) -> Result<&LayoutPlan, PopulationError> {
+    population
+        .iter()
+        .min_by_key(|plan| plan.get_total_cost())
+        .ok_or(PopulationError::EmptyPopulation)
+}
+
+fn select_by_tournament<'a, R: Rng + ?Sized>(
+    population: &'a [LayoutPlan],
+    tournament_size: usize,
+    rng: &mut R,
+) -> Result<&'a LayoutPlan, PopulationError> {
+    if tournament_size > population.len() {
+        return Err(PopulationError::TournamentTooLarge {
+            tournament_size: tournament_size as u64,
+            population_size: population.len(),
+        });
+    }
+
+    population
+        .choose_multiple(rng, tournament_size)
+        .min_by_key(|plan| plan.get_total_cost())
+        .ok_or(PopulationError::EmptyPopulation)
+}
+
+fn calculate_statistics(
+    population: &[LayoutPlan],
+) -> Result<(Fitness, Fitness, f64, f64), PopulationError> {
+    let costs: Vec<Fitness> = population.iter().map(LayoutPlan::get_total_cost).collect();
+
+    let best = *costs.iter().min().ok_or(PopulationError::EmptyPopulation)?;
+    let worst = *costs.iter().max().ok_or(PopulationError::EmptyPopulation)?;
+    let average = costs.iter().map(|cost| cost.value()).sum::<f64>() / costs.len() as f64;
+    let deviation = (costs
+        .iter()
+        .map(|cost| (average - cost.value()).powi(2))
+        .sum::<f64>()
+        / costs.len() as f64)
+        .sqrt();
+
+    Ok((best, worst, average, deviation))
+}
+
+/// {"periods": [{"flow": "p1_flow.json", "cost": "p1_cost.json"}, ...], "rearrangement_cost": 50,
+///  "rearrangement_costs": [{"machine": 3, "cost": 200}]}
+/// the flow and cost paths are relative to the periods file, the grid, the distance model,
+/// the footprints and the REL chart are those of the base instance
+pub fn read_dynamic_instance(
+    path: &str,
+    base_layout: &FacilityLayout,
+    machines: &[u64],
+    default_cost: Option<f64>,
+) -> Result<DynamicInstance, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    let value: Value = serde_json::from_str(&contents).map_err(|source| ParseError::Json {
+        path: path.to_string(),
+        source,
+    })?;
+
+    let entries = |key: &str| match value.get(key) {
+        Some(Value::Array(entries)) => Ok(entries.as_slice()),
+        None => Ok(&[][..]),
+        Some(_) => Err(ParseError::NotAnArray {
+            path: path.to_string(),
+        }),
+    };
+    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    let relative = |entry: &Value, index: usize, field: &'static str| {
+        entry
+            .get(field)
+            .and_then(Value::as_str)
+            .map(|file| directory.join(file).to_string_lossy().into_owned())
+            .ok_or(ParseError::MissingField {
+                path: path.to_string(),
+                index,
+                field,
+            })
+    };
+
+    let mut periods: Vec<FacilityLayout> = Vec::new();
+    for (index, entry) in entries("periods")?.iter().enumerate() {
+        let mut period = parse_flows(
+            &relative(entry, index, "flow")?,
+            &relative(entry, index, "cost")?,
+            machines,
+            default_cost,
+        )?;
+        period.distance_model = base_layout.distance_model.clone();
+        period.footprints = base_layout.footprints.clone();
+        period.rel_chart = base_layout.rel_chart.clone();
+        periods.push(period);
+    }
+
+    if periods.is_empty() {
+        return Err(ParseError::Malformed {
+            path: path.to_string(),
+            reason: String::from("there are no periods"),
+        });
+    }
+
+    let rearrangement_cost = match value.get("rearrangement_cost") {
+        Some(_) => parse_amount(&value, path, 0, "rearrangement_cost")?,
+        None => 0.0,
+    };
+
+    let mut rearrangement_costs: HashMap<u64, f64> = HashMap::new();
+    for (index, entry) in entries("rearrangement_costs")?.iter().enumerate() {
+        let machine = parse_field(entry, path, index, "machine")?;
+        if !machines.contains(&machine) {
+            return Err(ParseError::UnknownMachine {
+                path: path.to_string(),
+                index,
+                machine,
+            });
+        }
+        rearrangement_costs.insert(machine, parse_amount(entry, path, index, "cost")?);
+    }
+
+    Ok(DynamicInstance {
+        periods,
+        rearrangement_cost,
+        rearrangement_costs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceModel;
+    use crate::facility_layout::FacilityFlow;
+
+    // two periods on a 2x2 grid, the flow between 0 and 1 is heavy in the first period and the one
+    // between 0 and 3 in the second, moving machine 3 costs more than the others
+    fn instance() -> DynamicInstance {
+        let period = |dest| FacilityLayout {
+            facility_flows: vec![FacilityFlow {
+                source: 0,
+                dest,
+                amount: 10.0,
+                cost: 1.0,
+            }],
+            distance_model: DistanceModel::Manhattan,
+            footprints: None,
+            rel_chart: None,
+        };
+
+        DynamicInstance {
+            periods: vec![period(1), period(3)],
+            rearrangement_cost: 5.0,
+            rearrangement_costs: HashMap::from([(3, 12.0)]),
+        }
+    }
+
+    #[test]
+    fn charges_every_moved_machine() {
+        let instance = instance();
+        let first = Facility::new(vec![Some(0), Some(1), Some(2), Some(3)], 2);
+        let second = Facility::new(vec![Some(0), Some(3), Some(2), Some(1)], 2);
+
+        assert_eq!(instance.calculate_rearrangement_cost(&first, &first), 0.0);
+        assert_eq!(instance.calculate_rearrangement_cost(&first, &second), 17.0);
+    }
+
+    #[test]
+    fn adds_the_rearrangement_to_the_flow_cost() {
+        let instance = instance();
+        let first = Facility::new(vec![Some(0), Some(1), Some(2), Some(3)], 2);
+        let second = Facility::new(vec![Some(0), Some(3), Some(2), Some(1)], 2);
+
+        // keeping the first layout puts machine 3 diagonal to machine 0 in the second period
+        let kept = LayoutPlan::new(vec![first.clone(), first.clone()], &instance);
+        assert_eq!(kept.flow_cost.value(), 30.0);
+        assert!(kept.rearrangement_cost.is_sign_positive());
+        assert_eq!(kept.get_total_cost().value(), 30.0);
+
+        let rearranged = LayoutPlan::new(vec![first, second], &instance);
+        assert_eq!(rearranged.flow_cost.value(), 20.0);
+        assert_eq!(rearranged.rearrangement_cost, 17.0);
+        assert_eq!(rearranged.get_total_cost().value(), 37.0);
+    }
+}
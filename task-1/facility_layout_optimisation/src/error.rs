@@ -0,0 +1,296 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// an instance file could not be read or does not have the expected structure
+#[derive(Debug)]
+pub enum ParseError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+    Archive {
+        path: String,
+        source: zip::result::ZipError,
+    },
+    UnknownInstance {
+        path: String,
+        name: String,
+    },
+    NotAnArray {
+        path: String,
+    },
+    Malformed {
+        path: String,
+        reason: String,
+    },
+    MissingField {
+        path: String,
+        index: usize,
+        field: &'static str,
+    },
+    InvalidField {
+        path: String,
+        index: usize,
+        field: &'static str,
+        value: String,
+    },
+    TooManyMachines {
+        path: String,
+        machines: usize,
+        cells: u64,
+    },
+    UnknownMachine {
+        path: String,
+        index: usize,
+        machine: u64,
+    },
+    DuplicatePair {
+        path: String,
+        index: usize,
+        source: u64,
+        dest: u64,
+    },
+    MissingCost {
+        path: String,
+        source: u64,
+        dest: u64,
+    },
+    UnmatchedCost {
+        path: String,
+        index: usize,
+        source: u64,
+        dest: u64,
+    },
+    /// the file is well-formed, but describes a facility the algorithms cannot work with
+    Config {
+        path: String,
+        source: ConfigError,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io { path, source } => write!(f, "{}: {}", path, source),
+            ParseError::Json { path, source } => write!(f, "{}: invalid JSON: {}", path, source),
+            ParseError::Archive { path, source } => {
+                write!(f, "{}: invalid zip archive: {}", path, source)
+            }
+            ParseError::UnknownInstance { path, name } => {
+                write!(f, "{}: the archive has no entry '{}'", path, name)
+            }
+            ParseError::NotAnArray { path } => {
+                write!(f, "{}: expected a JSON array of entries", path)
+            }
+            ParseError::Malformed { path, reason } => write!(f, "{}: {}", path, reason),
+            ParseError::MissingField { path, index, field } => {
+                write!(
+                    f,
+                    "{}: entry {} is missing the '{}' field",
+                    path, index, field
+                )
+            }
+            ParseError::InvalidField {
+                path,
+                index,
+                field,
+                value,
+            } => write!(
+                f,
+                "{}: entry {} has an invalid '{}' field: {} is not a non-negative number",
+                path, index, field, value
+            ),
+            ParseError::TooManyMachines {
+                path,
+                machines,
+                cells,
+            } => write!(
+                f,
+                "{}: {} machines do not fit into {} cells",
+                path, machines, cells
+            ),
+            ParseError::UnknownMachine {
+                path,
+                index,
+                machine,
+            } => write!(
+                f,
+                "{}: entry {} refers to machine {}, which is not part of the facility",
+                path, index, machine
+            ),
+            ParseError::DuplicatePair {
+                path,
+                index,
+                source,
+                dest,
+            } => write!(
+                f,
+                "{}: entry {} repeats the pair {} -> {}",
+                path, index, source, dest
+            ),
+            ParseError::MissingCost { path, source, dest } => write!(
+                f,
+                "{}: there is no cost for the flow {} -> {} and no default cost was given",
+                path, source, dest
+            ),
+            ParseError::UnmatchedCost {
+                path,
+                index,
+                source,
+                dest,
+            } => write!(
+                f,
+                "{}: entry {} gives a cost for the pair {} -> {}, which has no flow",
+                path, index, source, dest
+            ),
+            ParseError::Config { path, source } => write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            ParseError::Json { source, .. } => Some(source),
+            ParseError::Archive { source, .. } => Some(source),
+            ParseError::Config { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    TooManyMachines { machines: usize, cells: u64 },
+    UnknownMachine { machine: u64 },
+    InvalidCell { cell: usize },
+    ConflictingFixedMachine { machine: u64, cell: usize },
+    FootprintsTooLarge { area: u64, cells: u64 },
+    InvalidDensity { density: f64 },
+    ZeroMaximum,
+    InvalidEvaporation { evaporation: f64 },
+    TooFewRows { height: u64 },
+    TooFewConfigurations { configurations: usize },
+    TooFewRuns { configuration: String, runs: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::TooManyMachines { machines, cells } => write!(
+                f,
+                "{} machines do not fit into {} cells - width * height must be greater or equal the machine count",
+                machines, cells
+            ),
+            ConfigError::UnknownMachine { machine } => {
+                write!(f, "machine {} is not part of the facility", machine)
+            }
+            ConfigError::InvalidCell { cell } => write!(
+                f,
+                "cell {} is outside of the facility or cannot host a fixed machine",
+                cell
+            ),
+            ConfigError::ConflictingFixedMachine { machine, cell } => write!(
+                f,
+                "machine {} fixed to cell {} conflicts with another fixed machine",
+                machine, cell
+            ),
+            ConfigError::FootprintsTooLarge { area, cells } => write!(
+                f,
+                "the machine footprints cover {} cells, but there are only {} free cells",
+                area, cells
+            ),
+            ConfigError::InvalidDensity { density } => {
+                write!(f, "the flow density {} is not between 0 and 1", density)
+            }
+            ConfigError::ZeroMaximum => write!(f, "the maximal amount and cost must be at least 1"),
+            ConfigError::InvalidEvaporation { evaporation } => write!(
+                f,
+                "the evaporation {} is not greater than 0 and at most 1",
+                evaporation
+            ),
+            ConfigError::TooFewRows { height } => write!(
+                f,
+                "the crossover needs a facility of at least 2 rows, not {}",
+                height
+            ),
+            ConfigError::TooFewConfigurations { configurations } => write!(
+                f,
+                "a comparison needs at least two configurations, not {}",
+                configurations
+            ),
+            ConfigError::TooFewRuns { configuration, runs } => write!(
+                f,
+                "{}: a comparison needs at least two runs per configuration, not {}",
+                configuration, runs
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[derive(Debug)]
+pub enum PopulationError {
+    EmptyPopulation,
+    TournamentTooLarge {
+        tournament_size: u64,
+        population_size: usize,
+    },
+    RouletteMiss,
+    PopulationTooSmall {
+        population_size: usize,
+        required: usize,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for PopulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PopulationError::EmptyPopulation => write!(f, "there are no specimens to choose from"),
+            PopulationError::TournamentTooLarge {
+                tournament_size,
+                population_size,
+            } => write!(
+                f,
+                "the tournament size ({}) must be less than or equal the total specimen count ({})",
+                tournament_size, population_size
+            ),
+            PopulationError::RouletteMiss => write!(
+                f,
+                "no specimen has likelihood_bound as high as the roulette guess"
+            ),
+            PopulationError::PopulationTooSmall {
+                population_size,
+                required,
+            } => write!(
+                f,
+                "the population has {} specimens, but at least {} are needed",
+                population_size, required
+            ),
+            PopulationError::Io(source) => write!(f, "unable to write the statistics: {}", source),
+        }
+    }
+}
+
+impl Error for PopulationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PopulationError::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PopulationError {
+    fn from(error: io::Error) -> Self {
+        PopulationError::Io(error)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::fitness::Fitness;
+use crate::genome::Genome;
+
+use std::cell::Cell;
+
+/// counts the fitness evaluations of a run - every solver evaluates through it, so that runs can be
+/// compared by the work they did rather than by their generations, and stops once the budget is spent
+/// the genetic algorithm stops evaluating as soon as the budget is spent, the other solvers check it
+/// between their iterations, so the last one may overshoot it by its evaluations
+/// the swap deltas of the ants' local search are partial evaluations and are not counted, its full
+/// evaluations with footprints or a REL chart are
+pub struct Evaluator<'a, G: Genome> {
+    pub context: &'a G::Context<'a>,
+    evaluations: Cell<u64>,
+    budget: Option<u64>,
+}
+
+impl<'a, G: Genome> Evaluator<'a, G> {
+    pub fn new(context: &'a G::Context<'a>, budget: Option<u64>) -> Self {
+        Evaluator::resume(context, budget, 0)
+    }
+
+    /// continues counting from the evaluations of an interrupted run
+    pub fn resume(context: &'a G::Context<'a>, budget: Option<u64>, evaluations: u64) -> Self {
+        Evaluator {
+            context,
+            evaluations: Cell::new(evaluations),
+            budget,
+        }
+    }
+
+    pub fn evaluate(&self, genome: &G) -> Fitness {
+        self.evaluate_with(|context| genome.evaluate(context))
+    }
+
+    /// counts an evaluation the solver does itself, e.g. the objective vector of a layout for
+    /// NSGA-II or the total cost of a dynamic plan, which are not the fitness of a genome
+    pub fn evaluate_with<T>(&self, evaluate: impl FnOnce(&G::Context<'a>) -> T) -> T {
+        self.evaluations.set(self.evaluations.get() + 1);
+        evaluate(self.context)
+    }
+
+    pub fn get_evaluations(&self) -> u64 {
+        self.evaluations.get()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.budget
+            .is_some_and(|budget| self.evaluations.get() >= budget)
+    }
+}
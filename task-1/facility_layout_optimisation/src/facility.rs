@@ -1,5 +1,10 @@
+use crate::distance_model::DistanceModel;
 use crate::facility_layout::FacilityLayout;
-use std::collections::HashSet;
+use crate::fitness::Fitness;
+use crate::footprint::Footprints;
+use crate::genome::{Genome, LayoutGenome};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
 
 use rand::seq::SliceRandom;
 use rand::Rng;
@@ -7,31 +12,39 @@ use rand::Rng;
 use crate::facility_configuration::Dimensions;
 use std::iter::zip;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Facility {
     interior: Vec<Option<u64>>,
     width: u64,
 }
 
 impl Facility {
-    // generates a new facility with random machine arrangement
-    pub fn generate_randomised_facility(dimensions: &Dimensions) -> Self {
+    /// generates a new facility with random machine arrangement
+    /// the fixed machines are put on their cells and the blocked cells are left empty
+    pub fn generate_randomised_facility<R: Rng + ?Sized>(
+        dimensions: &Dimensions,
+        rng: &mut R,
+    ) -> Self {
         let interior_size = dimensions.height * dimensions.width;
 
-        if (interior_size as usize) < dimensions.machines.len() {
+        if dimensions.get_free_cells().len() < dimensions.get_movable_machines().len() {
             panic!("The interior must be able to fit all of the machines!")
         }
 
-        let mut rng = rand::thread_rng();
-        let mut shuffled_facility_indices: Vec<u64> = (0..interior_size).collect();
-        shuffled_facility_indices.shuffle(&mut rng);
+        let mut shuffled_facility_indices: Vec<usize> = dimensions.get_free_cells();
+        shuffled_facility_indices.shuffle(rng);
 
-        let interior = zip(shuffled_facility_indices, &dimensions.machines).fold(
-            vec![None; interior_size as usize],
+        let mut fixed_interior = vec![None; interior_size as usize];
+        for &(machine, cell) in &dimensions.fixed_machines {
+            fixed_interior[cell] = Some(machine);
+        }
+
+        let interior = zip(shuffled_facility_indices, dimensions.get_movable_machines()).fold(
+            fixed_interior,
             |mut acc_vec: Vec<Option<u64>>, indices| {
                 let (facility_index, machine) = indices;
 
-                acc_vec[facility_index as usize] = Some(*machine);
+                acc_vec[facility_index] = Some(machine);
                 acc_vec
             },
         );
@@ -42,24 +55,56 @@ impl Facility {
         }
     }
 
-    pub fn calculate_fitness(&self, facility_layout: &FacilityLayout) -> u64 {
+    /// the interior is given row by row
+    pub fn new(interior: Vec<Option<u64>>, width: u64) -> Self {
+        Facility { interior, width }
+    }
+
+    /// with a REL chart, the flow cost and the closeness penalty are added up by their weights
+    pub fn calculate_fitness(&self, facility_layout: &FacilityLayout) -> Fitness {
+        let flow_cost = self.calculate_flow_cost(facility_layout);
+
+        Fitness::new(match &facility_layout.rel_chart {
+            Some(rel_chart) => {
+                rel_chart.flow_weight * flow_cost
+                    + rel_chart.closeness_weight
+                        * rel_chart.penalty(self, facility_layout.footprints.as_ref())
+            }
+            None => flow_cost,
+        })
+    }
+
+    /// the sum of distance * amount * cost over all flows
+    pub fn calculate_flow_cost(&self, facility_layout: &FacilityLayout) -> f64 {
+        if let Some(footprints) = &facility_layout.footprints {
+            return self.calculate_footprint_flow_cost(facility_layout, footprints);
+        }
+
         facility_layout
             .facility_flows
             .iter()
-            .fold(0, |total_fitness, facility_flow| {
+            .fold(0.0, |total_fitness, facility_flow| {
                 total_fitness
                     + self
-                        .calculate_distance(facility_flow.source, facility_flow.dest)
-                        .unwrap_or(0)
+                        .calculate_distance(
+                            facility_flow.source,
+                            facility_flow.dest,
+                            &facility_layout.distance_model,
+                        )
+                        .unwrap_or(0.0)
                         * facility_flow.amount
                         * facility_flow.cost
             })
     }
 
-    pub fn crossover(&self, other: &Facility) -> (Facility, Facility) {
+    pub fn crossover<R: Rng + ?Sized>(
+        &self,
+        other: &Facility,
+        dimensions: &Dimensions,
+        rng: &mut R,
+    ) -> (Facility, Facility) {
         // TODO - check if both interiors are of same dimensions
 
-        let mut rng = rand::thread_rng();
         // crossover takes place on this row, and all to the bottom of it
         // exclude the 0th row, so that crossover always takes place
         let crossover_row = rng.gen_range(1..self.get_height());
@@ -70,19 +115,111 @@ impl Facility {
         // normalise the crossovers
         let empties = self.count_empty_spaces();
         // TODO - get_uniques() calls here can be replaces by .specimens.flatten()?
-        self_crossover.normalise(self.get_uniques(), empties as u64);
-        other_crossover.normalise(other.get_uniques(), empties as u64);
+        self_crossover.normalise(self.get_uniques(), empties as u64, dimensions, rng);
+        other_crossover.normalise(other.get_uniques(), empties as u64, dimensions, rng);
 
         (self_crossover, other_crossover)
     }
 
+    /// the children inherit a random rectangle of cells from one parent and as much of the other
+    /// parent as fits around it, so that neighbourhoods in both directions survive the crossover
+    /// every child is a permutation of its parents' machines and empty cells, so it needs no repair
+    pub fn block_crossover<R: Rng + ?Sized>(
+        &self,
+        other: &Facility,
+        dimensions: &Dimensions,
+        rng: &mut R,
+    ) -> (Facility, Facility) {
+        let mut rows = [
+            rng.gen_range(0..self.get_height()),
+            rng.gen_range(0..self.get_height()),
+        ];
+        let mut columns = [rng.gen_range(0..self.width), rng.gen_range(0..self.width)];
+        rows.sort_unstable();
+        columns.sort_unstable();
+
+        let block: Vec<bool> = (0..self.interior.len() as u64)
+            .map(|cell| {
+                let (row, column) = (cell / self.width, cell % self.width);
+                (rows[0]..=rows[1]).contains(&row) && (columns[0]..=columns[1]).contains(&column)
+            })
+            .collect();
+
+        (
+            self.create_block_crossover(other, &block, dimensions),
+            other.create_block_crossover(self, &block, dimensions),
+        )
+    }
+
+    // the cells of the block come from self, every other free cell keeps the contents it has in
+    // the other parent unless they are already used, the cells left are filled with the unused
+    // contents in the order of the other parent
+    fn create_block_crossover(
+        &self,
+        other: &Facility,
+        block: &[bool],
+        dimensions: &Dimensions,
+    ) -> Facility {
+        let free_cells = dimensions.get_free_cells();
+        let mut interior = other.interior.clone();
+
+        let mut used: HashSet<u64> = HashSet::new();
+        let mut empties = free_cells
+            .iter()
+            .filter(|cell| self.interior[**cell].is_none())
+            .count();
+
+        for &cell in free_cells.iter().filter(|cell| block[**cell]) {
+            interior[cell] = self.interior[cell];
+            match self.interior[cell] {
+                Some(machine) => {
+                    used.insert(machine);
+                }
+                None => empties -= 1,
+            }
+        }
+
+        let mut unfilled: Vec<usize> = Vec::new();
+        for &cell in free_cells.iter().filter(|cell| !block[**cell]) {
+            match other.interior[cell] {
+                Some(machine) if !used.contains(&machine) => {
+                    used.insert(machine);
+                }
+                None if empties > 0 => empties -= 1,
+                _ => unfilled.push(cell),
+            }
+        }
+
+        let mut leftovers: Vec<Option<u64>> = free_cells
+            .iter()
+            .filter_map(|cell| other.interior[*cell])
+            .filter(|machine| !used.contains(machine))
+            .map(Some)
+            .collect();
+        leftovers.extend(vec![None; empties]);
+
+        for (cell, contents) in unfilled.into_iter().zip(leftovers) {
+            interior[cell] = contents;
+        }
+
+        Facility {
+            interior,
+            width: self.width,
+        }
+    }
+
     // mutates every cell by +-1 with a mutation_factor probability
     // TODO don't assume cells are values [n,m] with offset = 1? use cell_vec instead
-    pub fn mutate(&mut self, mutation_factor: f64, max_cell_value: u64) {
+    pub fn mutate<R: Rng + ?Sized>(
+        &mut self,
+        mutation_factor: f64,
+        max_cell_value: u64,
+        dimensions: &Dimensions,
+        rng: &mut R,
+    ) {
         // TODO check if 0 < mutation_factor <= 100
 
         let overflow = max_cell_value + 1;
-        let mut rng = rand::thread_rng();
 
         let original_uniques = self.get_uniques();
 
@@ -108,21 +245,110 @@ impl Facility {
             })
             .collect();
 
-        self.normalise(original_uniques, self.count_empty_spaces() as u64);
+        self.normalise(
+            original_uniques,
+            self.count_empty_spaces() as u64,
+            dimensions,
+            rng,
+        );
     }
 
     pub fn find_max_machine(&self) -> Option<&u64> {
         self.interior.iter().flatten().max()
     }
 
-    fn calculate_distance(&self, from: u64, to: u64) -> Option<u64> {
-        let (from_x, from_y) = self.position(|&machine| machine == Some(from))?;
-        let (to_x, to_y) = self.position(|&machine| machine == Some(to))?;
+    pub fn get_width(&self) -> u64 {
+        self.width
+    }
 
-        Some(
-            ((from_x as isize - to_x as isize).abs() + (from_y as isize - to_y as isize).abs())
-                as u64,
-        )
+    pub fn get_height(&self) -> u64 {
+        (self.interior.len() as u64) / self.width
+    }
+
+    /// the machine standing on the given cell, if any
+    pub fn get_machine(&self, row: u64, column: u64) -> Option<u64> {
+        self.interior[(row * self.width + column) as usize]
+    }
+
+    /// the (row, column) of the cell the machine stands on
+    pub fn locate(&self, machine: u64) -> Option<(u64, u64)> {
+        self.position(|&cell| cell == Some(machine))
+    }
+
+    /// the index of the cell the machine stands on, counting row by row
+    pub fn cell_of(&self, machine: u64) -> Option<usize> {
+        self.interior.iter().position(|&cell| cell == Some(machine))
+    }
+
+    /// whether some cell of the first machine shares an edge with some cell of the second one
+    pub fn are_adjacent(&self, first: u64, second: u64) -> bool {
+        let width = self.width as usize;
+
+        self.interior
+            .iter()
+            .enumerate()
+            .filter(|(_, machine)| **machine == Some(first))
+            .any(|(cell, _)| {
+                let (row, column) = (cell / width, cell % width);
+                let neighbours = [
+                    (row > 0).then(|| cell - width),
+                    Some(cell + width),
+                    (column > 0).then(|| cell - 1),
+                    (column + 1 < width).then(|| cell + 1),
+                ];
+
+                neighbours
+                    .into_iter()
+                    .flatten()
+                    .any(|neighbour| self.interior.get(neighbour) == Some(&Some(second)))
+            })
+    }
+
+    // the flows run between the anchor points of the decoded rectangles,
+    // a facility which cannot be decoded costs as if every flow ran further than the largest
+    // distance of the model, so that it is worse than any facility which can
+    fn calculate_footprint_flow_cost(
+        &self,
+        facility_layout: &FacilityLayout,
+        footprints: &Footprints,
+    ) -> f64 {
+        // without a placement there are no anchors, so every flow falls back to the span
+        let anchors: HashMap<u64, (f64, f64)> = footprints
+            .place(self)
+            .iter()
+            .flatten()
+            .map(|placed| (placed.machine, footprints.anchor_point(placed)))
+            .collect();
+        let span = facility_layout
+            .distance_model
+            .max_distance(self.get_width(), self.get_height())
+            + 1.0;
+
+        facility_layout
+            .facility_flows
+            .iter()
+            .fold(0.0, |total_fitness, facility_flow| {
+                let distance = match (
+                    anchors.get(&facility_flow.source),
+                    anchors.get(&facility_flow.dest),
+                ) {
+                    (Some(from), Some(to)) => facility_layout
+                        .distance_model
+                        .point_distance(self.width, *from, *to),
+                    _ => span,
+                };
+
+                total_fitness + distance * facility_flow.amount * facility_flow.cost
+            })
+    }
+
+    fn calculate_distance(
+        &self,
+        from: u64,
+        to: u64,
+        distance_model: &DistanceModel,
+    ) -> Option<f64> {
+        Some(distance_model.distance(self.width, self.cell_of(from)?, self.cell_of(to)?))
     }
 
     fn position<P>(&self, predicate: P) -> Option<(u64, u64)>
@@ -135,21 +361,17 @@ impl Facility {
             .map(|i| (i as u64 / self.width, i as u64 % self.width))
     }
 
-    fn get_height(&self) -> u64 {
-        (self.interior.len() as u64) / self.width
-    }
-
     fn create_crossover(&self, other: &Facility, crossover_row: u64) -> Facility {
         // TODO less imperatively?
         let mut crossover = Vec::new();
 
         for i in 0..(crossover_row * self.width) {
-            crossover.push(self.interior[(i as usize)]);
+            crossover.push(self.interior[i as usize]);
         }
 
         // FIXME usize -> u64 cast potentially unsafe!
         for i in (crossover_row * self.width)..(self.interior.len() as u64) {
-            crossover.push(other.interior[(i as usize)]);
+            crossover.push(other.interior[i as usize]);
         }
 
         Facility {
@@ -169,18 +391,47 @@ impl Facility {
         uniques
     }
 
-    fn normalise(&mut self, mut uniques_in_parent: Vec<u64>, empties_in_parent: u64) {
-        let mut rng = rand::thread_rng();
-
+    fn normalise<R: Rng + ?Sized>(
+        &mut self,
+        mut uniques_in_parent: Vec<u64>,
+        empties_in_parent: u64,
+        dimensions: &Dimensions,
+        rng: &mut R,
+    ) {
         let uniques_in_normalised = self.get_uniques();
 
         uniques_in_parent.retain(|x| !uniques_in_normalised.contains(x));
-        uniques_in_parent.shuffle(&mut rng);
+        uniques_in_parent.shuffle(rng);
 
         let missing_empties =
-            (self.count_empty_spaces() as i64 - empties_in_parent as i64).abs() as u64;
+            (self.count_empty_spaces() as i64 - empties_in_parent as i64).unsigned_abs();
 
         self.remove_duplicates(uniques_in_parent, missing_empties);
+        self.repair_constraints(dimensions, rng);
+    }
+
+    // puts the fixed machines back onto their cells and moves machines off the blocked cells
+    fn repair_constraints<R: Rng + ?Sized>(&mut self, dimensions: &Dimensions, rng: &mut R) {
+        for &(machine, cell) in &dimensions.fixed_machines {
+            if let Some(current_cell) = self.cell_of(machine) {
+                self.interior.swap(current_cell, cell);
+            }
+        }
+
+        for &blocked_cell in &dimensions.blocked_cells {
+            if let Some(machine) = self.interior[blocked_cell].take() {
+                let empty_cells: Vec<usize> = dimensions
+                    .get_free_cells()
+                    .into_iter()
+                    .filter(|cell| self.interior[*cell].is_none())
+                    .collect();
+
+                // there is always an empty free cell, since all machines fit into the free cells
+                if let Some(cell) = empty_cells.choose(rng) {
+                    self.interior[*cell] = Some(machine);
+                }
+            }
+        }
     }
 
     fn remove_duplicates(&mut self, mut free_machines: Vec<u64>, mut missing_empties: u64) {
@@ -221,3 +472,216 @@ impl Facility {
         self.interior.iter().filter(|elem| elem.is_none()).count()
     }
 }
+
+pub fn generate_randomised_facilities<R: Rng + ?Sized>(
+    dimensions: &Dimensions,
+    population_size: u32,
+    rng: &mut R,
+) -> Vec<Facility> {
+    (0..population_size)
+        .map(|_x| Facility::generate_randomised_facility(dimensions, rng))
+        .collect()
+}
+
+/// how two facilities are crossed over as a [`Genome`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Crossover {
+    /// Facility::crossover, the children swap all the rows below a random one
+    Row,
+    /// Facility::block_crossover, the children swap a random rectangle
+    Block,
+}
+
+/// what a facility needs to know to evolve as a [`Genome`]
+pub struct LayoutContext<'a> {
+    pub dimensions: &'a Dimensions,
+    pub facility_layout: &'a FacilityLayout,
+    pub crossover: Crossover,
+    // the highest machine number, mutated machines wrap around it
+    max_machine: u64,
+}
+
+impl<'a> LayoutContext<'a> {
+    pub fn new(dimensions: &'a Dimensions, facility_layout: &'a FacilityLayout) -> Self {
+        LayoutContext {
+            dimensions,
+            facility_layout,
+            crossover: Crossover::Row,
+            max_machine: dimensions.machines.iter().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+impl Genome for Facility {
+    type Context<'a> = LayoutContext<'a>;
+
+    fn random<R: Rng + ?Sized>(context: &LayoutContext, rng: &mut R) -> Self {
+        Facility::generate_randomised_facility(context.dimensions, rng)
+    }
+
+    fn crossover<R: Rng + ?Sized>(
+        &self,
+        other: &Self,
+        context: &LayoutContext,
+        rng: &mut R,
+    ) -> (Self, Self) {
+        match context.crossover {
+            Crossover::Row => Facility::crossover(self, other, context.dimensions, rng),
+            Crossover::Block => self.block_crossover(other, context.dimensions, rng),
+        }
+    }
+
+    fn mutate<R: Rng + ?Sized>(
+        &mut self,
+        mutation_factor: f64,
+        context: &LayoutContext,
+        rng: &mut R,
+    ) {
+        Facility::mutate(
+            self,
+            mutation_factor,
+            context.max_machine,
+            context.dimensions,
+            rng,
+        )
+    }
+
+    fn evaluate(&self, context: &LayoutContext) -> Fitness {
+        self.calculate_fitness(context.facility_layout)
+    }
+}
+
+impl LayoutGenome for Facility {
+    fn to_facility(&self, _dimensions: &Dimensions) -> Facility {
+        self.clone()
+    }
+
+    // the cells row by row, null for the empty ones
+    fn to_value(&self) -> Value {
+        json!({
+            "width": self.width,
+            "cells": self.interior,
+        })
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        let interior = value["cells"]
+            .as_array()?
+            .iter()
+            .map(|cell| match cell {
+                Value::Null => Some(None),
+                cell => cell.as_u64().map(Some),
+            })
+            .collect::<Option<Vec<Option<u64>>>>()?;
+
+        Some(Facility::new(interior, value["width"].as_u64()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceMatrix;
+    use crate::facility_layout::FacilityFlow;
+    use crate::footprint::{Anchor, MachineFootprint};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn free_contents(facility: &Facility, dimensions: &Dimensions) -> Vec<Option<u64>> {
+        let mut contents: Vec<Option<u64>> = dimensions
+            .get_free_cells()
+            .into_iter()
+            .map(|cell| facility.interior[cell])
+            .collect();
+        contents.sort_unstable();
+        contents
+    }
+
+    #[test]
+    fn block_crossover_permutes_the_free_cells() {
+        let layouts = [
+            (Vec::new(), Vec::new()),
+            (vec![(3, 0), (7, 12)], Vec::new()),
+            (Vec::new(), vec![5, 6, 18]),
+            (vec![(3, 0), (7, 12)], vec![5, 6, 18]),
+        ];
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        for (fixed_machines, blocked_cells) in layouts {
+            // with and without empty cells among the free ones
+            for machine_count in [12, 20 - blocked_cells.len() as u64] {
+                let dimensions = Dimensions {
+                    width: 5,
+                    height: 4,
+                    machines: (0..machine_count).collect(),
+                    fixed_machines: fixed_machines.clone(),
+                    blocked_cells: blocked_cells.clone(),
+                };
+
+                for _ in 0..200 {
+                    let first = Facility::generate_randomised_facility(&dimensions, &mut rng);
+                    let second = Facility::generate_randomised_facility(&dimensions, &mut rng);
+                    let expected = free_contents(&first, &dimensions);
+
+                    let (first_child, second_child) =
+                        first.block_crossover(&second, &dimensions, &mut rng);
+
+                    for child in [&first_child, &second_child] {
+                        assert_eq!(free_contents(child, &dimensions), expected);
+                        for &(machine, cell) in &dimensions.fixed_machines {
+                            assert_eq!(child.interior[cell], Some(machine));
+                        }
+                        for &cell in &dimensions.blocked_cells {
+                            assert_eq!(child.interior[cell], None);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // machines 1 and 2 are two cells wide, all the cells of the 3x2 grid are 100 apart - much
+    // further than the grid itself is wide
+    #[test]
+    fn charges_unplaceable_footprints_more_than_any_placement() {
+        let cells = 6;
+        let distances = (0..cells * cells)
+            .map(|i| if i / cells == i % cells { 0.0 } else { 100.0 })
+            .collect();
+        let wide = MachineFootprint {
+            width: 2,
+            height: 1,
+            rotatable: false,
+            io_cell: None,
+        };
+        let facility_layout = FacilityLayout {
+            facility_flows: [(0, 1), (0, 2), (1, 2)]
+                .into_iter()
+                .map(|(source, dest)| FacilityFlow {
+                    source,
+                    dest,
+                    amount: 1.0,
+                    cost: 1.0,
+                })
+                .collect(),
+            distance_model: DistanceModel::Matrix(DistanceMatrix::new(cells, distances).unwrap()),
+            footprints: Some(Footprints {
+                machines: HashMap::from([(1, wide.clone()), (2, wide)]),
+                anchor: Anchor::Centroid,
+                blocked_cells: Vec::new(),
+                fixed_machines: Vec::new(),
+            }),
+            rel_chart: None,
+        };
+
+        let placeable = Facility::new(vec![Some(1), None, Some(0), Some(2), None, None], 3);
+        // machine 2 finds no two free cells next to each other anymore
+        let unplaceable = Facility::new(vec![None, Some(0), None, Some(1), None, Some(2)], 3);
+
+        assert_eq!(placeable.calculate_fitness(&facility_layout).value(), 300.0);
+        assert!(
+            unplaceable.calculate_fitness(&facility_layout)
+                > placeable.calculate_fitness(&facility_layout)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::ConfigError;
+
 pub struct FacilityConfig {
     layout: Layout,
     pub dimensions: Dimensions,
@@ -10,9 +12,12 @@ impl FacilityConfig {
         width: u64,
         height: u64,
         machines: Vec<u64>,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, ConfigError> {
         if machines.len() > (width * height) as usize {
-            return Err("Width * height must be greater or equal the machine count.");
+            return Err(ConfigError::TooManyMachines {
+                machines: machines.len(),
+                cells: width * height,
+            });
         }
 
         Ok(FacilityConfig {
@@ -24,12 +29,14 @@ impl FacilityConfig {
                 width,
                 height,
                 machines,
+                fixed_machines: Vec::new(),
+                blocked_cells: Vec::new(),
             },
         })
     }
 
     // TODO: could this somehow be made const?
-    pub fn get_easy_config() -> Result<Self, &'static str> {
+    pub fn get_easy_config() -> Result<Self, ConfigError> {
         FacilityConfig::new(
             String::from("data/easy_flow.json"),
             String::from("data/easy_cost.json"),
@@ -39,7 +46,7 @@ impl FacilityConfig {
         )
     }
 
-    pub fn get_flat_config() -> Result<Self, &'static str> {
+    pub fn get_flat_config() -> Result<Self, ConfigError> {
         FacilityConfig::new(
             String::from("data/flat_flow.json"),
             String::from("data/flat_cost.json"),
@@ -49,7 +56,7 @@ impl FacilityConfig {
         )
     }
 
-    pub fn get_hard_config() -> Result<Self, &'static str> {
+    pub fn get_hard_config() -> Result<Self, ConfigError> {
         FacilityConfig::new(
             String::from("data/hard_flow.json"),
             String::from("data/hard_cost.json"),
@@ -59,6 +66,17 @@ impl FacilityConfig {
         )
     }
 
+    /// the dimensions of the instances described in the data/README.pdf
+    pub fn get_known_dimensions(name: &str) -> Option<Dimensions> {
+        match name {
+            "easy" => FacilityConfig::get_easy_config().ok(),
+            "flat" => FacilityConfig::get_flat_config().ok(),
+            "hard" => FacilityConfig::get_hard_config().ok(),
+            _ => None,
+        }
+        .map(|config| config.dimensions)
+    }
+
     pub fn get_flow_path(&self) -> &String {
         &self.layout.flow_path
     }
@@ -73,8 +91,68 @@ struct Layout {
     pub cost_path: String,
 }
 
+#[derive(Clone, Debug)]
 pub struct Dimensions {
     pub width: u64,
     pub height: u64,
     pub machines: Vec<u64>,
+    /// (machine, cell) pairs of machines which cannot be moved, the cells are numbered row by row
+    pub fixed_machines: Vec<(u64, usize)>,
+    /// cells which cannot host any machine, e.g. pillars
+    pub blocked_cells: Vec<usize>,
+}
+
+impl Dimensions {
+    /// the cells which the movable machines can be placed on
+    pub fn get_free_cells(&self) -> Vec<usize> {
+        (0..(self.width * self.height) as usize)
+            .filter(|cell| !self.blocked_cells.contains(cell))
+            .filter(|cell| !self.fixed_machines.iter().any(|(_, fixed)| fixed == cell))
+            .collect()
+    }
+
+    pub fn get_movable_machines(&self) -> Vec<u64> {
+        self.machines
+            .iter()
+            .filter(|machine| {
+                !self
+                    .fixed_machines
+                    .iter()
+                    .any(|(fixed, _)| fixed == *machine)
+            })
+            .copied()
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let cells = (self.width * self.height) as usize;
+
+        for (i, &(machine, cell)) in self.fixed_machines.iter().enumerate() {
+            if !self.machines.contains(&machine) {
+                return Err(ConfigError::UnknownMachine { machine });
+            }
+            if cell >= cells || self.blocked_cells.contains(&cell) {
+                return Err(ConfigError::InvalidCell { cell });
+            }
+            if self.fixed_machines[..i]
+                .iter()
+                .any(|(other_machine, other_cell)| *other_machine == machine || *other_cell == cell)
+            {
+                return Err(ConfigError::ConflictingFixedMachine { machine, cell });
+            }
+        }
+
+        if let Some(&cell) = self.blocked_cells.iter().find(|cell| **cell >= cells) {
+            return Err(ConfigError::InvalidCell { cell });
+        }
+
+        if self.get_movable_machines().len() > self.get_free_cells().len() {
+            return Err(ConfigError::TooManyMachines {
+                machines: self.machines.len(),
+                cells: (self.get_free_cells().len() + self.fixed_machines.len()) as u64,
+            });
+        }
+
+        Ok(())
+    }
 }
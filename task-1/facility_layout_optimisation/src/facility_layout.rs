@@ -1,12 +1,22 @@
+use crate::distance_model::DistanceModel;
+use crate::footprint::Footprints;
+use crate::rel_chart::RelChart;
+
 #[derive(Debug)]
 pub struct FacilityLayout {
     pub facility_flows: Vec<FacilityFlow>,
+    pub distance_model: DistanceModel,
+    /// the machines occupying several cells, None if every machine occupies a single cell
+    pub footprints: Option<Footprints>,
+    /// the closeness ratings between machines, None if only the flows matter
+    pub rel_chart: Option<RelChart>,
 }
 
 #[derive(Debug)]
 pub struct FacilityFlow {
     pub source: u64,
     pub dest: u64,
-    pub amount: u64,
-    pub cost: u64,
+    /// the amounts and the costs may be fractional, e.g. a cost per metre
+    pub amount: f64,
+    pub cost: f64,
 }
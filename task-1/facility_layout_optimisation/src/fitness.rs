@@ -0,0 +1,73 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::Add;
+
+/// the fitness of a solution, lower values are better
+/// it is a real number, so that fractional amounts, costs and distances are kept exactly and a sum
+/// too large for the integers does not wrap around - it loses precision and ends at infinity
+/// the values are totally ordered like by f64::total_cmp, a NaN comes after every other value
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fitness(f64);
+
+impl Fitness {
+    pub fn new(value: f64) -> Self {
+        Fitness(value)
+    }
+
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl PartialEq for Fitness {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Fitness {}
+
+impl PartialOrd for Fitness {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fitness {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Add for Fitness {
+    type Output = Fitness;
+
+    fn add(self, other: Fitness) -> Fitness {
+        Fitness(self.0 + other.0)
+    }
+}
+
+impl Sum for Fitness {
+    fn sum<I: Iterator<Item = Fitness>>(iter: I) -> Fitness {
+        iter.fold(Fitness::default(), Add::add)
+    }
+}
+
+/// whole values are written without a fractional part, like the integer costs of the instances,
+/// the others with at most 6 decimals unless a precision is given, which hides the rounding
+/// errors of the sums
+impl fmt::Display for Fitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.precision().is_some() {
+            return fmt::Display::fmt(&self.0, f);
+        }
+
+        let value = format!("{:.6}", self.0);
+        if value.contains('.') {
+            f.pad(value.trim_end_matches('0').trim_end_matches('.'))
+        } else {
+            f.pad(&value)
+        }
+    }
+}
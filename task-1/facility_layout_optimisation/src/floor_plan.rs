@@ -0,0 +1,228 @@
+use crate::distance_model::{DistanceMatrix, DistanceModel};
+use crate::error::{ConfigError, ParseError};
+use serde_json::Value;
+
+use std::collections::VecDeque;
+use std::fs;
+
+const FLOOR: char = '.';
+const WALL: char = '#';
+
+/// the footprint of a facility which is not a full rectangle, e.g. an L-shaped hall
+/// every row of the map is a line of '.' for floor cells and '#' for walls, either as a plain
+/// text file or as a JSON array of strings
+pub struct FloorPlan {
+    pub width: u64,
+    pub height: u64,
+    floor: Vec<bool>,
+}
+
+impl FloorPlan {
+    /// the cells which cannot host any machine, numbered row by row
+    pub fn get_wall_cells(&self) -> Vec<usize> {
+        (0..self.floor.len())
+            .filter(|cell| !self.floor[*cell])
+            .collect()
+    }
+
+    /// the shortest walk between every pair of cells, moving between neighbouring floor cells
+    /// separate halls are not connected by any walk, their cells keep the manhattan distance instead
+    pub fn walking_distances(&self) -> DistanceMatrix {
+        let size = self.floor.len();
+        let mut distances: Vec<f64> = Vec::with_capacity(size * size);
+
+        for from in 0..size {
+            let walks = self.walk_from(from);
+
+            distances.extend((0..size).map(|to| match walks[to] {
+                Some(steps) => steps as f64,
+                None => DistanceModel::Manhattan.distance(self.width, from, to),
+            }));
+        }
+
+        // there is a distance for every pair of cells, so the matrix is always square
+        DistanceMatrix::new(size, distances).unwrap_or_else(|| {
+            DistanceMatrix::from_model(&DistanceModel::Manhattan, self.width, self.height)
+        })
+    }
+
+    // breadth first search over the floor cells, None for the cells which cannot be reached
+    fn walk_from(&self, start: usize) -> Vec<Option<u64>> {
+        let width = self.width as usize;
+        let mut steps: Vec<Option<u64>> = vec![None; self.floor.len()];
+        if !self.floor[start] {
+            return steps;
+        }
+
+        let mut queue = VecDeque::from([start]);
+        steps[start] = Some(0);
+
+        while let Some(cell) = queue.pop_front() {
+            let (row, column) = (cell / width, cell % width);
+            let neighbours = [
+                (row > 0).then(|| cell - width),
+                (row + 1 < self.height as usize).then(|| cell + width),
+                (column > 0).then(|| cell - 1),
+                (column + 1 < width).then(|| cell + 1),
+            ];
+
+            for neighbour in neighbours.into_iter().flatten() {
+                if self.floor[neighbour] && steps[neighbour].is_none() {
+                    steps[neighbour] = steps[cell].map(|steps| steps + 1);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        steps
+    }
+}
+
+pub fn read_floor_plan(path: &str) -> Result<FloorPlan, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+
+    let rows: Vec<String> = if path.ends_with(".json") {
+        match serde_json::from_str(&contents) {
+            Ok(Value::Array(rows)) => rows
+                .iter()
+                .map(|row| {
+                    row.as_str()
+                        .map(String::from)
+                        .ok_or_else(|| malformed(path, "every row has to be a string"))
+                })
+                .collect::<Result<Vec<String>, ParseError>>()?,
+            Ok(_) => {
+                return Err(ParseError::NotAnArray {
+                    path: path.to_string(),
+                })
+            }
+            Err(source) => {
+                return Err(ParseError::Json {
+                    path: path.to_string(),
+                    source,
+                })
+            }
+        }
+    } else {
+        contents
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    };
+
+    let width = rows.first().map(|row| row.chars().count()).unwrap_or(0);
+    if width == 0 {
+        return Err(malformed(path, "the floor plan is empty"));
+    }
+    // the crossover cuts the facility between two rows
+    if rows.len() < 2 {
+        return Err(ParseError::Config {
+            path: path.to_string(),
+            source: ConfigError::TooFewRows {
+                height: rows.len() as u64,
+            },
+        });
+    }
+
+    let mut floor: Vec<bool> = Vec::with_capacity(width * rows.len());
+    for (index, row) in rows.iter().enumerate() {
+        if row.chars().count() != width {
+            return Err(malformed(
+                path,
+                &format!(
+                    "row {} is not {} cells wide like the first row",
+                    index, width
+                ),
+            ));
+        }
+
+        for cell in row.chars() {
+            match cell {
+                FLOOR => floor.push(true),
+                WALL => floor.push(false),
+                _ => {
+                    return Err(malformed(
+                        path,
+                        &format!(
+                            "row {} has the cell '{}', expected '{}' or '{}'",
+                            index, cell, FLOOR, WALL
+                        ),
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(FloorPlan {
+        width: width as u64,
+        height: rows.len() as u64,
+        floor,
+    })
+}
+
+fn malformed(path: &str, reason: &str) -> ParseError {
+    ParseError::Malformed {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor_plan(rows: &[&str]) -> FloorPlan {
+        FloorPlan {
+            width: rows[0].len() as u64,
+            height: rows.len() as u64,
+            floor: rows
+                .iter()
+                .flat_map(|row| row.chars().map(|cell| cell == FLOOR))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn walks_around_walls() {
+        let distances = floor_plan(&["...", "##.", "..."]).walking_distances();
+
+        // from the top left corner to the bottom left one, around the end of the wall
+        assert_eq!(distances.get(0, 6), 6.0);
+        assert_eq!(distances.get(6, 0), 6.0);
+        assert_eq!(distances.get(0, 2), 2.0);
+        assert_eq!(distances.get(4, 4), 0.0);
+    }
+
+    #[test]
+    fn falls_back_to_manhattan_between_separate_halls() {
+        let distances = floor_plan(&[".#.", ".#."]).walking_distances();
+
+        assert_eq!(distances.get(0, 3), 1.0);
+        assert_eq!(distances.get(0, 5), 3.0);
+        assert_eq!(distances.get(2, 3), 3.0);
+        // the wall cells cannot be walked from either
+        assert_eq!(distances.get(1, 5), 2.0);
+    }
+
+    #[test]
+    fn rejects_a_single_row() {
+        let path = std::env::temp_dir().join(format!("floor_plan_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "....\n").unwrap();
+        let floor_plan = read_floor_plan(path);
+        fs::remove_file(path).unwrap();
+
+        assert!(matches!(
+            floor_plan,
+            Err(ParseError::Config {
+                source: ConfigError::TooFewRows { height: 1 },
+                ..
+            })
+        ));
+    }
+}
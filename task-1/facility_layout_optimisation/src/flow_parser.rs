@@ -1,36 +1,464 @@
 use serde_json::Value;
 
+use crate::distance_model::{DistanceMatrix, DistanceModel};
+use crate::error::ParseError;
 use crate::facility_layout::{FacilityFlow, FacilityLayout};
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::iter::zip;
 
-pub fn parse_flows(flow_file_path: &str, cost_file_path: &str) -> FacilityLayout {
-    // read the JSON files into strings
-    let flow_contents = fs::read_to_string(flow_file_path).unwrap();
-    let cost_contents = fs::read_to_string(cost_file_path).unwrap();
+/// flows and costs are matched on their (source, dest) pair, the order of the entries does not matter
+/// flows without a cost entry take the default cost, if one is given
+/// the amounts and the costs may be fractional, but not negative
+pub fn parse_flows(
+    flow_file_path: &str,
+    cost_file_path: &str,
+    machines: &[u64],
+    default_cost: Option<f64>,
+) -> Result<FacilityLayout, ParseError> {
+    parse_flow_contents(
+        flow_file_path,
+        &read_file(flow_file_path)?,
+        cost_file_path,
+        &read_file(cost_file_path)?,
+        machines,
+        default_cost,
+    )
+}
 
+/// the paths are only used to report errors, the JSONs are already read into the contents
+pub fn parse_flow_contents(
+    flow_file_path: &str,
+    flow_contents: &str,
+    cost_file_path: &str,
+    cost_contents: &str,
+    machines: &[u64],
+    default_cost: Option<f64>,
+) -> Result<FacilityLayout, ParseError> {
     // parse the JSONs
-    let flows: Vec<Value> = serde_json::from_str(&flow_contents).unwrap();
-    let costs: Vec<Value> = serde_json::from_str(&cost_contents).unwrap();
+    let flows = parse_entries(flow_file_path, flow_contents)?;
+    let costs = parse_entries(cost_file_path, cost_contents)?;
+
+    let machines: HashSet<u64> = machines.iter().copied().collect();
+
+    // index the costs by their machine pair
+    let mut costs_by_pair: HashMap<(u64, u64), (usize, f64)> = HashMap::new();
+    for (index, cost) in costs.iter().enumerate() {
+        let pair = parse_pair(cost, cost_file_path, index, &machines)?;
+        let value = parse_amount(cost, cost_file_path, index, "cost")?;
+
+        if costs_by_pair.insert(pair, (index, value)).is_some() {
+            return Err(duplicate_pair(cost_file_path, index, pair));
+        }
+    }
 
     // map the JSONs to FacilityFlows
-    // TODO use map() instead of doing this imperatively, somehow?
+    let mut flow_pairs: HashSet<(u64, u64)> = HashSet::new();
     let mut facility_flows: Vec<FacilityFlow> = Vec::new();
 
-    for i in zip(&flows, &costs) {
-        let (flow, cost) = i;
-        facility_flows.push(parse_facility_flow(flow, cost));
+    for (index, flow) in flows.iter().enumerate() {
+        let pair = parse_pair(flow, flow_file_path, index, &machines)?;
+        let amount = parse_amount(flow, flow_file_path, index, "amount")?;
+
+        if !flow_pairs.insert(pair) {
+            return Err(duplicate_pair(flow_file_path, index, pair));
+        }
+
+        let cost = match (costs_by_pair.get(&pair), default_cost) {
+            (Some((_, cost)), _) => *cost,
+            (None, Some(default_cost)) => default_cost,
+            (None, None) => {
+                return Err(ParseError::MissingCost {
+                    path: cost_file_path.to_string(),
+                    source: pair.0,
+                    dest: pair.1,
+                })
+            }
+        };
+
+        facility_flows.push(FacilityFlow {
+            source: pair.0,
+            dest: pair.1,
+            amount,
+            cost,
+        });
+    }
+
+    // every cost has to belong to some flow, otherwise the files do not describe the same instance
+    if let Some((pair, (index, _))) = costs_by_pair
+        .iter()
+        .filter(|(pair, _)| !flow_pairs.contains(pair))
+        .min_by_key(|(_, (index, _))| *index)
+    {
+        return Err(ParseError::UnmatchedCost {
+            path: cost_file_path.to_string(),
+            index: *index,
+            source: pair.0,
+            dest: pair.1,
+        });
     }
 
-    FacilityLayout { facility_flows }
+    Ok(FacilityLayout {
+        facility_flows,
+        distance_model: DistanceModel::Manhattan,
+        footprints: None,
+        rel_chart: None,
+    })
 }
 
-fn parse_facility_flow(flow: &Value, cost: &Value) -> FacilityFlow {
-    FacilityFlow {
-        source: flow["source"].as_u64().unwrap(),
-        dest: flow["dest"].as_u64().unwrap(),
-        amount: flow["amount"].as_u64().unwrap(),
-        cost: cost["cost"].as_u64().unwrap(),
+fn parse_pair(
+    entry: &Value,
+    path: &str,
+    index: usize,
+    machines: &HashSet<u64>,
+) -> Result<(u64, u64), ParseError> {
+    let source = parse_field(entry, path, index, "source")?;
+    let dest = parse_field(entry, path, index, "dest")?;
+
+    for machine in [source, dest] {
+        if !machines.contains(&machine) {
+            return Err(ParseError::UnknownMachine {
+                path: path.to_string(),
+                index,
+                machine,
+            });
+        }
+    }
+
+    Ok((source, dest))
+}
+
+fn duplicate_pair(path: &str, index: usize, pair: (u64, u64)) -> ParseError {
+    ParseError::DuplicatePair {
+        path: path.to_string(),
+        index,
+        source: pair.0,
+        dest: pair.1,
+    }
+}
+
+/// every machine mentioned as a source or a destination in a flow file, in ascending order
+pub fn collect_machines(flow_file_path: &str, flow_contents: &str) -> Result<Vec<u64>, ParseError> {
+    let mut machines: Vec<u64> = Vec::new();
+
+    for (index, flow) in parse_entries(flow_file_path, flow_contents)?
+        .iter()
+        .enumerate()
+    {
+        machines.push(parse_field(flow, flow_file_path, index, "source")?);
+        machines.push(parse_field(flow, flow_file_path, index, "dest")?);
+    }
+
+    machines.sort_unstable();
+    machines.dedup();
+    Ok(machines)
+}
+
+/// a JSON array of rows, the distance between every pair of cells (numbered row by row)
+pub fn parse_distance_matrix(path: &str, cells: usize) -> Result<DistanceMatrix, ParseError> {
+    let rows = parse_entries(path, &read_file(path)?)?;
+    if rows.len() != cells {
+        return Err(ParseError::Malformed {
+            path: path.to_string(),
+            reason: format!(
+                "expected {} rows, one for every cell, got {}",
+                cells,
+                rows.len()
+            ),
+        });
+    }
+
+    let mut distances: Vec<f64> = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let row = row
+            .as_array()
+            .filter(|row| row.len() == cells)
+            .ok_or_else(|| ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("row {} does not have {} distances", index, cells),
+            })?;
+
+        for value in row {
+            distances.push(value.as_f64().filter(|d| *d >= 0.0).ok_or_else(|| {
+                ParseError::InvalidField {
+                    path: path.to_string(),
+                    index,
+                    field: "distance",
+                    value: value.to_string(),
+                }
+            })?);
+        }
+    }
+
+    // the row lengths are already checked, so the matrix is always square
+    DistanceMatrix::new(cells, distances).ok_or_else(|| ParseError::Malformed {
+        path: path.to_string(),
+        reason: String::from("the distance matrix is not square"),
+    })
+}
+
+/// {"fixed": [{"machine": 3, "row": 0, "column": 1}], "blocked": [{"row": 2, "column": 2}]}
+/// both lists are optional, the cells are returned as indices numbered row by row
+#[allow(clippy::type_complexity)]
+pub fn parse_constraints(
+    path: &str,
+    width: u64,
+) -> Result<(Vec<(u64, usize)>, Vec<usize>), ParseError> {
+    let value: Value =
+        serde_json::from_str(&read_file(path)?).map_err(|source| ParseError::Json {
+            path: path.to_string(),
+            source,
+        })?;
+
+    let entries = |key: &str| -> Result<Vec<Value>, ParseError> {
+        match value.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(entries)) => Ok(entries.clone()),
+            Some(_) => Err(ParseError::NotAnArray {
+                path: path.to_string(),
+            }),
+        }
+    };
+
+    let cell = |entry: &Value, index: usize| -> Result<usize, ParseError> {
+        let row = parse_field(entry, path, index, "row")?;
+        let column = parse_field(entry, path, index, "column")?;
+
+        if column >= width {
+            return Err(ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!(
+                    "entry {} is in column {}, but the facility only has {} columns",
+                    index, column, width
+                ),
+            });
+        }
+
+        Ok((row * width + column) as usize)
+    };
+
+    let fixed_machines = entries("fixed")?
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            Ok((
+                parse_field(entry, path, index, "machine")?,
+                cell(entry, index)?,
+            ))
+        })
+        .collect::<Result<Vec<(u64, usize)>, ParseError>>()?;
+    let blocked_cells = entries("blocked")?
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| cell(entry, index))
+        .collect::<Result<Vec<usize>, ParseError>>()?;
+
+    Ok((fixed_machines, blocked_cells))
+}
+
+/// [{"machine": 3, "level": 2}], the machines which are not listed are silent
+pub fn parse_noise_levels(path: &str, machines: &[u64]) -> Result<Vec<(u64, u64)>, ParseError> {
+    parse_entries(path, &read_file(path)?)?
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let machine = parse_field(entry, path, index, "machine")?;
+            if !machines.contains(&machine) {
+                return Err(ParseError::UnknownMachine {
+                    path: path.to_string(),
+                    index,
+                    machine,
+                });
+            }
+
+            Ok((machine, parse_field(entry, path, index, "level")?))
+        })
+        .collect()
+}
+
+fn read_file(path: &str) -> Result<String, ParseError> {
+    fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn parse_entries(path: &str, contents: &str) -> Result<Vec<Value>, ParseError> {
+    match serde_json::from_str(contents) {
+        Ok(Value::Array(entries)) => Ok(entries),
+        Ok(_) => Err(ParseError::NotAnArray {
+            path: path.to_string(),
+        }),
+        Err(source) => Err(ParseError::Json {
+            path: path.to_string(),
+            source,
+        }),
+    }
+}
+
+pub fn parse_field(
+    entry: &Value,
+    path: &str,
+    index: usize,
+    field: &'static str,
+) -> Result<u64, ParseError> {
+    let value = get_field(entry, path, index, field)?;
+
+    value.as_u64().ok_or_else(|| ParseError::InvalidField {
+        path: path.to_string(),
+        index,
+        field,
+        value: value.to_string(),
+    })
+}
+
+/// like parse_field, but the number may be fractional, e.g. 2.5 - it must not be negative
+pub fn parse_amount(
+    entry: &Value,
+    path: &str,
+    index: usize,
+    field: &'static str,
+) -> Result<f64, ParseError> {
+    let value = get_field(entry, path, index, field)?;
+
+    value
+        .as_f64()
+        .filter(|amount| *amount >= 0.0)
+        .ok_or_else(|| ParseError::InvalidField {
+            path: path.to_string(),
+            index,
+            field,
+            value: value.to_string(),
+        })
+}
+
+fn get_field<'a>(
+    entry: &'a Value,
+    path: &str,
+    index: usize,
+    field: &'static str,
+) -> Result<&'a Value, ParseError> {
+    entry.get(field).ok_or_else(|| ParseError::MissingField {
+        path: path.to_string(),
+        index,
+        field,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOWS: &str = r#"[
+        {"source": 0, "dest": 1, "amount": 4},
+        {"source": 1, "dest": 2, "amount": 2.5}
+    ]"#;
+
+    fn parse(
+        flows: &str,
+        costs: &str,
+        default_cost: Option<f64>,
+    ) -> Result<FacilityLayout, ParseError> {
+        parse_flow_contents(
+            "flow.json",
+            flows,
+            "cost.json",
+            costs,
+            &[0, 1, 2],
+            default_cost,
+        )
+    }
+
+    fn flows(facility_layout: &FacilityLayout) -> Vec<(u64, u64, f64, f64)> {
+        facility_layout
+            .facility_flows
+            .iter()
+            .map(|flow| (flow.source, flow.dest, flow.amount, flow.cost))
+            .collect()
+    }
+
+    #[test]
+    fn joins_the_costs_by_their_pair() {
+        let costs =
+            r#"[{"source": 1, "dest": 2, "cost": 3}, {"source": 0, "dest": 1, "cost": 0.5}]"#;
+
+        assert_eq!(
+            flows(&parse(FLOWS, costs, None).unwrap()),
+            vec![(0, 1, 4.0, 0.5), (1, 2, 2.5, 3.0)]
+        );
+    }
+
+    #[test]
+    fn takes_the_default_cost_for_missing_pairs() {
+        let costs = r#"[{"source": 1, "dest": 2, "cost": 3}]"#;
+
+        assert!(matches!(
+            parse(FLOWS, costs, None),
+            Err(ParseError::MissingCost {
+                source: 0,
+                dest: 1,
+                ..
+            })
+        ));
+        assert_eq!(
+            flows(&parse(FLOWS, costs, Some(2.0)).unwrap()),
+            vec![(0, 1, 4.0, 2.0), (1, 2, 2.5, 3.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_pairs() {
+        let flows =
+            r#"[{"source": 0, "dest": 1, "amount": 4}, {"source": 0, "dest": 1, "amount": 1}]"#;
+        let costs = r#"[{"source": 0, "dest": 1, "cost": 1}, {"source": 0, "dest": 1, "cost": 2}]"#;
+
+        assert!(matches!(
+            parse(flows, "[]", Some(1.0)),
+            Err(ParseError::DuplicatePair { ref path, index: 1, source: 0, dest: 1 })
+                if path == "flow.json"
+        ));
+        assert!(matches!(
+            parse(FLOWS, costs, Some(1.0)),
+            Err(ParseError::DuplicatePair { ref path, index: 1, source: 0, dest: 1 })
+                if path == "cost.json"
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_machines() {
+        let flows = r#"[{"source": 0, "dest": 7, "amount": 4}]"#;
+        let costs = r#"[{"source": 3, "dest": 1, "cost": 1}]"#;
+
+        assert!(matches!(
+            parse(flows, "[]", Some(1.0)),
+            Err(ParseError::UnknownMachine {
+                index: 0,
+                machine: 7,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(FLOWS, costs, Some(1.0)),
+            Err(ParseError::UnknownMachine {
+                index: 0,
+                machine: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_costs_without_a_flow() {
+        let costs = r#"[{"source": 0, "dest": 1, "cost": 1}, {"source": 2, "dest": 0, "cost": 1}]"#;
+
+        assert!(matches!(
+            parse(FLOWS, costs, Some(1.0)),
+            Err(ParseError::UnmatchedCost {
+                index: 1,
+                source: 2,
+                dest: 0,
+                ..
+            })
+        ));
     }
 }
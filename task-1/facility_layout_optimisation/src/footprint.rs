@@ -0,0 +1,360 @@
+use crate::error::{ConfigError, ParseError};
+use crate::facility::Facility;
+use crate::flow_parser::parse_field;
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::fs;
+
+/// which point of a machine the flows start and end at
+#[derive(Clone, Copy, Debug)]
+pub enum Anchor {
+    Centroid,
+    /// the machine's I/O cell, or its centroid if it has none
+    IoPoint,
+}
+
+/// a machine which occupies a rectangle of cells, all the other machines occupy a single cell
+#[derive(Clone, Debug)]
+pub struct MachineFootprint {
+    pub width: u64,
+    pub height: u64,
+    pub rotatable: bool,
+    /// (row, column) of the I/O cell inside the unrotated rectangle
+    pub io_cell: Option<(u64, u64)>,
+}
+
+/// the footprints of the machines, together with the cells no machine may cover
+/// and the machines which stay on their cells
+#[derive(Clone, Debug)]
+pub struct Footprints {
+    pub machines: HashMap<u64, MachineFootprint>,
+    pub anchor: Anchor,
+    pub blocked_cells: Vec<usize>,
+    /// (machine, cell) pairs like Dimensions::fixed_machines, the cell is the top left corner
+    pub fixed_machines: Vec<(u64, usize)>,
+}
+
+/// a machine's rectangle on the grid, rotated rectangles have their width and height swapped
+pub struct PlacedMachine {
+    pub machine: u64,
+    pub row: u64,
+    pub column: u64,
+    pub width: u64,
+    pub height: u64,
+    pub rotated: bool,
+}
+
+impl Footprints {
+    /// the layout is decoded from the facility by taking the machines row by row, each one is put
+    /// with its top left corner onto its own cell, or onto the next cell where it fits if it does not,
+    /// so the rectangles never overlap whatever the crossover and the mutation did to the facility
+    /// the fixed machines are placed first, onto their own cells, so no other machine can take them
+    /// returns None if some machine does not fit anywhere anymore, or a fixed one not on its cell
+    pub fn place(&self, facility: &Facility) -> Option<Vec<PlacedMachine>> {
+        let (width, height) = (facility.get_width(), facility.get_height());
+        let cells = (width * height) as usize;
+        let mut covered = vec![false; cells];
+        for cell in &self.blocked_cells {
+            covered[*cell] = true;
+        }
+
+        let mut placement: Vec<PlacedMachine> = Vec::new();
+        for &(machine, cell) in &self.fixed_machines {
+            let (row, column) = (cell as u64 / width, cell as u64 % width);
+            let placed = self
+                .orientations(machine)
+                .into_iter()
+                .find_map(|(w, h, rotated)| {
+                    fits(&covered, width, height, row, column, w, h).then_some(PlacedMachine {
+                        machine,
+                        row,
+                        column,
+                        width: w,
+                        height: h,
+                        rotated,
+                    })
+                })?;
+
+            cover(&mut covered, width, &placed);
+            placement.push(placed);
+        }
+
+        for start in 0..cells {
+            let machine = match facility.get_machine(start as u64 / width, start as u64 % width) {
+                Some(machine) => machine,
+                None => continue,
+            };
+            if self
+                .fixed_machines
+                .iter()
+                .any(|(fixed_machine, _)| *fixed_machine == machine)
+            {
+                continue;
+            }
+
+            let orientations = self.orientations(machine);
+            let placed = (0..cells)
+                .map(|offset| (start + offset) % cells)
+                .find_map(|cell| {
+                    let (row, column) = (cell as u64 / width, cell as u64 % width);
+                    orientations.iter().find_map(|&(w, h, rotated)| {
+                        fits(&covered, width, height, row, column, w, h).then_some(PlacedMachine {
+                            machine,
+                            row,
+                            column,
+                            width: w,
+                            height: h,
+                            rotated,
+                        })
+                    })
+                })?;
+
+            cover(&mut covered, width, &placed);
+            placement.push(placed);
+        }
+
+        Some(placement)
+    }
+
+    // the (width, height, rotated) rectangles the machine can take, the unrotated one first
+    fn orientations(&self, machine: u64) -> Vec<(u64, u64, bool)> {
+        match self.machines.get(&machine) {
+            Some(footprint) if footprint.rotatable && footprint.width != footprint.height => vec![
+                (footprint.width, footprint.height, false),
+                (footprint.height, footprint.width, true),
+            ],
+            Some(footprint) => vec![(footprint.width, footprint.height, false)],
+            None => vec![(1, 1, false)],
+        }
+    }
+
+    /// the (row, column) point the flows of a placed machine attach to, cell centres are at .5
+    pub fn anchor_point(&self, placed: &PlacedMachine) -> (f64, f64) {
+        let io_cell = match self.anchor {
+            Anchor::Centroid => None,
+            Anchor::IoPoint => self
+                .machines
+                .get(&placed.machine)
+                .and_then(|footprint| footprint.io_cell),
+        };
+
+        match io_cell {
+            // the rectangle is rotated clockwise, so the rows of the I/O cell turn into columns
+            Some((row, column)) if placed.rotated => (
+                (placed.row + column) as f64 + 0.5,
+                (placed.column + placed.width - 1 - row) as f64 + 0.5,
+            ),
+            Some((row, column)) => (
+                (placed.row + row) as f64 + 0.5,
+                (placed.column + column) as f64 + 0.5,
+            ),
+            None => (
+                placed.row as f64 + placed.height as f64 / 2.0,
+                placed.column as f64 + placed.width as f64 / 2.0,
+            ),
+        }
+    }
+
+    /// the machines have to be known and all the rectangles have to fit into the free cells together
+    pub fn validate(&self, machines: &[u64], free_cells: usize) -> Result<(), ConfigError> {
+        if let Some(machine) = self
+            .machines
+            .keys()
+            .find(|machine| !machines.contains(machine))
+        {
+            return Err(ConfigError::UnknownMachine { machine: *machine });
+        }
+
+        let area: u64 = machines
+            .iter()
+            .map(|machine| match self.machines.get(machine) {
+                Some(footprint) => footprint.width * footprint.height,
+                None => 1,
+            })
+            .sum();
+
+        if area > free_cells as u64 {
+            return Err(ConfigError::FootprintsTooLarge {
+                area,
+                cells: free_cells as u64,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// draws every machine onto all the cells of its rectangle, e.g. for render_ascii
+pub fn placed_facility(placement: &[PlacedMachine], width: u64, height: u64) -> Facility {
+    let mut interior = vec![None; (width * height) as usize];
+
+    for placed in placement {
+        for row in placed.row..(placed.row + placed.height) {
+            for column in placed.column..(placed.column + placed.width) {
+                interior[(row * width + column) as usize] = Some(placed.machine);
+            }
+        }
+    }
+
+    Facility::new(interior, width)
+}
+
+fn cover(covered: &mut [bool], width: u64, placed: &PlacedMachine) {
+    for row in placed.row..(placed.row + placed.height) {
+        for column in placed.column..(placed.column + placed.width) {
+            covered[(row * width + column) as usize] = true;
+        }
+    }
+}
+
+fn fits(
+    covered: &[bool],
+    width: u64,
+    height: u64,
+    row: u64,
+    column: u64,
+    machine_width: u64,
+    machine_height: u64,
+) -> bool {
+    row + machine_height <= height
+        && column + machine_width <= width
+        && (row..(row + machine_height)).all(|row| {
+            (column..(column + machine_width))
+                .all(|column| !covered[(row * width + column) as usize])
+        })
+}
+
+/// {"anchor": "centroid" | "io", "machines": [{"machine": 3, "width": 2, "height": 1,
+///  "rotatable": true, "io": {"row": 0, "column": 1}}]}
+/// the anchor defaults to the centroid, rotatable to false and io to none
+pub fn read_footprints(
+    path: &str,
+    blocked_cells: Vec<usize>,
+    fixed_machines: Vec<(u64, usize)>,
+) -> Result<Footprints, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    let value: Value = serde_json::from_str(&contents).map_err(|source| ParseError::Json {
+        path: path.to_string(),
+        source,
+    })?;
+
+    let anchor = match value.get("anchor").and_then(Value::as_str) {
+        None | Some("centroid") => Anchor::Centroid,
+        Some("io") => Anchor::IoPoint,
+        Some(anchor) => {
+            return Err(ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("unknown anchor '{}', expected centroid or io", anchor),
+            })
+        }
+    };
+
+    let entries = match value.get("machines") {
+        Some(Value::Array(entries)) => entries,
+        _ => {
+            return Err(ParseError::NotAnArray {
+                path: path.to_string(),
+            })
+        }
+    };
+
+    let mut machines: HashMap<u64, MachineFootprint> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let machine = parse_field(entry, path, index, "machine")?;
+        let width = parse_field(entry, path, index, "width")?;
+        let height = parse_field(entry, path, index, "height")?;
+        let rotatable = entry
+            .get("rotatable")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let io_cell = match entry.get("io") {
+            Some(io) => Some((
+                parse_field(io, path, index, "row")?,
+                parse_field(io, path, index, "column")?,
+            )),
+            None => None,
+        };
+
+        if width == 0 || height == 0 {
+            return Err(ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("entry {} has an empty footprint", index),
+            });
+        }
+        if io_cell.is_some_and(|(row, column)| row >= height || column >= width) {
+            return Err(ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("entry {} has its I/O cell outside of its footprint", index),
+            });
+        }
+
+        let footprint = MachineFootprint {
+            width,
+            height,
+            rotatable,
+            io_cell,
+        };
+        if machines.insert(machine, footprint).is_some() {
+            return Err(ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("entry {} repeats machine {}", index, machine),
+            });
+        }
+    }
+
+    Ok(Footprints {
+        machines,
+        anchor,
+        blocked_cells,
+        fixed_machines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // machine 1 is two cells wide
+    fn footprints(fixed_machines: Vec<(u64, usize)>) -> Footprints {
+        Footprints {
+            machines: HashMap::from([(
+                1,
+                MachineFootprint {
+                    width: 2,
+                    height: 1,
+                    rotatable: false,
+                    io_cell: None,
+                },
+            )]),
+            anchor: Anchor::Centroid,
+            blocked_cells: Vec::new(),
+            fixed_machines,
+        }
+    }
+
+    #[test]
+    fn keeps_fixed_machines_on_their_cells() {
+        let facility = Facility::new(vec![Some(1), Some(2), None, None], 4);
+        let placement = footprints(vec![(2, 1)]).place(&facility).unwrap();
+
+        let column = |machine: u64| {
+            placement
+                .iter()
+                .find(|placed| placed.machine == machine)
+                .map(|placed| placed.column)
+        };
+        assert_eq!(column(2), Some(1));
+        assert_eq!(column(1), Some(2));
+    }
+
+    #[test]
+    fn rejects_fixed_machines_which_do_not_fit() {
+        let facility = Facility::new(vec![Some(2), None, None, Some(1)], 4);
+
+        assert!(footprints(vec![(1, 3)]).place(&facility).is_none());
+    }
+}
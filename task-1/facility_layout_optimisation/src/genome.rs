@@ -0,0 +1,43 @@
+use crate::facility::{Facility, LayoutContext};
+use crate::facility_configuration::Dimensions;
+use crate::fitness::Fitness;
+use serde_json::Value;
+
+use rand::Rng;
+
+/// an encoding of a solution the genetic algorithm of [`crate::specimen::Simulation`] can evolve,
+/// the context is whatever the operators need to know about the problem, e.g. the instance
+/// lower fitness values are better, like the flow cost of a layout
+/// genomes are compared, so that the ones a mutation did not change are not evaluated again
+pub trait Genome: Clone + PartialEq {
+    type Context<'a>;
+
+    fn random<R: Rng + ?Sized>(context: &Self::Context<'_>, rng: &mut R) -> Self;
+
+    fn crossover<R: Rng + ?Sized>(
+        &self,
+        other: &Self,
+        context: &Self::Context<'_>,
+        rng: &mut R,
+    ) -> (Self, Self);
+
+    /// every gene changes with the probability of the mutation factor
+    fn mutate<R: Rng + ?Sized>(
+        &mut self,
+        mutation_factor: f64,
+        context: &Self::Context<'_>,
+        rng: &mut R,
+    );
+
+    fn evaluate(&self, context: &Self::Context<'_>) -> Fitness;
+}
+
+/// a genome which encodes a layout of an instance, so that the runs of every encoding can be
+/// rendered, improved by local search and checkpointed the same way
+pub trait LayoutGenome: for<'a> Genome<Context<'a> = LayoutContext<'a>> {
+    fn to_facility(&self, dimensions: &Dimensions) -> Facility;
+
+    fn to_value(&self) -> Value;
+
+    fn from_value(value: &Value) -> Option<Self>;
+}
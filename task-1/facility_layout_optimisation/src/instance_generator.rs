@@ -0,0 +1,188 @@
+use crate::error::ConfigError;
+use crate::facility_configuration::Dimensions;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// how the amounts or the costs of the generated flows are drawn, always from 1 to the maximum
+#[derive(Clone, Copy, Debug)]
+pub enum Distribution {
+    Uniform,
+    /// the machines form groups, the pairs within a group get high values and the others low ones
+    Clustered,
+    /// a heavy tail - most values are small and a few are close to the maximum
+    PowerLaw,
+}
+
+impl Distribution {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "uniform" => Some(Distribution::Uniform),
+            "clustered" => Some(Distribution::Clustered),
+            "power-law" => Some(Distribution::PowerLaw),
+            _ => None,
+        }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R, max: u64, same_cluster: bool) -> u64 {
+        match self {
+            Distribution::Uniform => rng.gen_range(1..=max),
+            Distribution::Clustered if same_cluster => rng.gen_range((max / 2).max(1)..=max),
+            Distribution::Clustered => rng.gen_range(1..=(max / 10).max(1)),
+            Distribution::PowerLaw => {
+                // the inverse transform of a pareto distribution with an exponent of 2.5
+                let value = (1.0 - rng.gen::<f64>()).powf(-1.0 / 1.5);
+                (value.floor() as u64).clamp(1, max)
+            }
+        }
+    }
+}
+
+pub struct GeneratorSettings {
+    pub dimensions: Dimensions,
+    /// the share of all machine pairs which have a flow between them
+    pub density: f64,
+    pub amounts: Distribution,
+    pub max_amount: u64,
+    pub costs: Distribution,
+    pub max_cost: u64,
+    pub seed: u64,
+}
+
+/// the flows and costs in the format of the data directory, every pair of machines is listed once
+pub struct GeneratedInstance {
+    pub flows: Vec<Value>,
+    pub costs: Vec<Value>,
+}
+
+impl GeneratorSettings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(ConfigError::InvalidDensity {
+                density: self.density,
+            });
+        }
+        if self.max_amount == 0 || self.max_cost == 0 {
+            return Err(ConfigError::ZeroMaximum);
+        }
+        // the crossover cuts the facility between two rows
+        if self.dimensions.height < 2 {
+            return Err(ConfigError::TooFewRows {
+                height: self.dimensions.height,
+            });
+        }
+
+        self.dimensions.validate()
+    }
+
+    /// the same settings always generate the same instance
+    pub fn generate(&self) -> GeneratedInstance {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let machines = &self.dimensions.machines;
+        // about as many groups as there are machines in every group
+        let cluster_count = (machines.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let clusters: Vec<usize> = machines
+            .iter()
+            .map(|_machine| rng.gen_range(0..cluster_count))
+            .collect();
+
+        let mut flows: Vec<Value> = Vec::new();
+        let mut costs: Vec<Value> = Vec::new();
+
+        for first in 0..machines.len() {
+            for second in (first + 1)..machines.len() {
+                if !rng.gen_bool(self.density) {
+                    continue;
+                }
+
+                let same_cluster = clusters[first] == clusters[second];
+                let amount = self.amounts.sample(&mut rng, self.max_amount, same_cluster);
+                let cost = self.costs.sample(&mut rng, self.max_cost, same_cluster);
+
+                flows.push(json!({
+                    "source": machines[first],
+                    "dest": machines[second],
+                    "amount": amount,
+                }));
+                costs.push(json!({
+                    "source": machines[first],
+                    "dest": machines[second],
+                    "cost": cost,
+                }));
+            }
+        }
+
+        GeneratedInstance { flows, costs }
+    }
+}
+
+impl GeneratedInstance {
+    /// writes `<name>_flow.json`, `<name>_cost.json` and `<name>_dimensions.json` into the directory
+    /// and returns the written paths, the machines are numbered from 0 so only their count is written
+    pub fn write(
+        &self,
+        directory: &str,
+        name: &str,
+        dimensions: &Dimensions,
+    ) -> io::Result<Vec<String>> {
+        fs::create_dir_all(directory)?;
+
+        let files = [
+            ("flow", json!(self.flows)),
+            ("cost", json!(self.costs)),
+            (
+                "dimensions",
+                json!({
+                    "width": dimensions.width,
+                    "height": dimensions.height,
+                    "machines": dimensions.machines.len(),
+                }),
+            ),
+        ];
+
+        let mut paths: Vec<String> = Vec::new();
+        for (suffix, value) in files {
+            let path = Path::new(directory).join(format!("{}_{}.json", name, suffix));
+            fs::write(&path, serde_json::to_string_pretty(&value)?)?;
+            paths.push(path.to_string_lossy().into_owned());
+        }
+
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(width: u64, height: u64) -> GeneratorSettings {
+        GeneratorSettings {
+            dimensions: Dimensions {
+                width,
+                height,
+                machines: (0..4).collect(),
+                fixed_machines: Vec::new(),
+                blocked_cells: Vec::new(),
+            },
+            density: 0.5,
+            amounts: Distribution::Uniform,
+            max_amount: 10,
+            costs: Distribution::Uniform,
+            max_cost: 10,
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn rejects_a_single_row() {
+        assert!(matches!(
+            settings(4, 1).validate(),
+            Err(ConfigError::TooFewRows { height: 1 })
+        ));
+        assert!(settings(2, 2).validate().is_ok());
+    }
+}
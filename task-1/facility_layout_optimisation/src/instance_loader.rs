@@ -0,0 +1,278 @@
+use crate::error::{ConfigError, ParseError};
+use crate::facility_configuration::{Dimensions, FacilityConfig};
+use crate::facility_layout::FacilityLayout;
+use crate::flow_parser::{collect_machines, parse_field, parse_flow_contents};
+use serde_json::Value;
+
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use zip::ZipArchive;
+
+const FLOW_SUFFIX: &str = "_flow.json";
+const COST_SUFFIX: &str = "_cost.json";
+// optional, {"width": 5, "height": 6, "machines": 24} - machines is either a count or a list of ids
+const DIMENSIONS_SUFFIX: &str = "_dimensions.json";
+
+/// a zip archive of instances, each one given as a `<name>_flow.json` and a `<name>_cost.json` entry
+pub struct InstanceArchive {
+    path: String,
+    archive: ZipArchive<File>,
+}
+
+pub struct Instance {
+    pub dimensions: Dimensions,
+    pub facility_layout: FacilityLayout,
+}
+
+impl InstanceArchive {
+    pub fn open(path: &str) -> Result<Self, ParseError> {
+        let file = File::open(path).map_err(|source| ParseError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        let archive = ZipArchive::new(file).map_err(|source| ParseError::Archive {
+            path: path.to_string(),
+            source,
+        })?;
+
+        Ok(InstanceArchive {
+            path: path.to_string(),
+            archive,
+        })
+    }
+
+    /// the names of all instances which have both a flow and a cost file, sorted
+    pub fn instance_names(&self) -> Vec<String> {
+        let entries: Vec<&str> = self.archive.file_names().collect();
+
+        let mut names: Vec<String> = entries
+            .iter()
+            .filter_map(|entry| entry.strip_suffix(FLOW_SUFFIX))
+            .filter(|name| entries.contains(&format!("{}{}", name, COST_SUFFIX).as_str()))
+            .map(String::from)
+            .collect();
+
+        names.sort();
+        names
+    }
+
+    pub fn load(&mut self, name: &str, default_cost: Option<f64>) -> Result<Instance, ParseError> {
+        let dimensions = self.dimensions(name)?;
+
+        let flow_file = format!("{}{}", name, FLOW_SUFFIX);
+        let cost_file = format!("{}{}", name, COST_SUFFIX);
+        let flow_contents = self.read_entry(&flow_file)?;
+        let cost_contents = self.read_entry(&cost_file)?;
+
+        let facility_layout = parse_flow_contents(
+            &self.entry_path(&flow_file),
+            &flow_contents,
+            &self.entry_path(&cost_file),
+            &cost_contents,
+            &dimensions.machines,
+            default_cost,
+        )?;
+
+        Ok(Instance {
+            dimensions,
+            facility_layout,
+        })
+    }
+
+    /// the grid dimensions are taken from the instance's dimensions file if the archive has one,
+    /// then from the instances known from the README, and are inferred from the flows otherwise
+    pub fn dimensions(&mut self, name: &str) -> Result<Dimensions, ParseError> {
+        let dimensions_file = format!("{}{}", name, DIMENSIONS_SUFFIX);
+        if self
+            .archive
+            .file_names()
+            .any(|entry| entry == dimensions_file)
+        {
+            let contents = self.read_entry(&dimensions_file)?;
+            return parse_dimensions(&self.entry_path(&dimensions_file), &contents);
+        }
+
+        let base_name = name.rsplit('/').next().unwrap_or(name);
+        if let Some(dimensions) = FacilityConfig::get_known_dimensions(base_name) {
+            return Ok(dimensions);
+        }
+
+        let flow_file = format!("{}{}", name, FLOW_SUFFIX);
+        let flow_contents = self.read_entry(&flow_file)?;
+        let machines = collect_machines(&self.entry_path(&flow_file), &flow_contents)?;
+
+        Ok(infer_dimensions(machines))
+    }
+
+    fn read_entry(&mut self, entry: &str) -> Result<String, ParseError> {
+        let path = self.entry_path(entry);
+
+        let mut file = match self.archive.by_name(entry) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => {
+                return Err(ParseError::UnknownInstance {
+                    path: self.path.clone(),
+                    name: entry.to_string(),
+                })
+            }
+            Err(source) => return Err(ParseError::Archive { path, source }),
+        };
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .map_err(|source| ParseError::Io { path, source })?;
+
+        Ok(contents)
+    }
+
+    fn entry_path(&self, entry: &str) -> String {
+        format!("{}/{}", self.path, entry)
+    }
+}
+
+/// the same files as in an archive, but lying in a directory, e.g. the generated instances
+pub fn load_from_directory(
+    directory: &str,
+    name: &str,
+    default_cost: Option<f64>,
+) -> Result<Instance, ParseError> {
+    let path = |suffix: &str| {
+        Path::new(directory)
+            .join(format!("{}{}", name, suffix))
+            .to_string_lossy()
+            .into_owned()
+    };
+    let read = |path: &str| {
+        fs::read_to_string(path).map_err(|source| ParseError::Io {
+            path: path.to_string(),
+            source,
+        })
+    };
+
+    let (flow_path, cost_path) = (path(FLOW_SUFFIX), path(COST_SUFFIX));
+    let flow_contents = read(&flow_path)?;
+
+    let dimensions_path = path(DIMENSIONS_SUFFIX);
+    let dimensions = if Path::new(&dimensions_path).exists() {
+        parse_dimensions(&dimensions_path, &read(&dimensions_path)?)?
+    } else if let Some(dimensions) = FacilityConfig::get_known_dimensions(name) {
+        dimensions
+    } else {
+        infer_dimensions(collect_machines(&flow_path, &flow_contents)?)
+    };
+
+    let facility_layout = parse_flow_contents(
+        &flow_path,
+        &flow_contents,
+        &cost_path,
+        &read(&cost_path)?,
+        &dimensions.machines,
+        default_cost,
+    )?;
+
+    Ok(Instance {
+        dimensions,
+        facility_layout,
+    })
+}
+
+fn parse_dimensions(path: &str, contents: &str) -> Result<Dimensions, ParseError> {
+    let value: Value = serde_json::from_str(contents).map_err(|source| ParseError::Json {
+        path: path.to_string(),
+        source,
+    })?;
+
+    let width = parse_field(&value, path, 0, "width")?;
+    let height = parse_field(&value, path, 0, "height")?;
+    let machines = match value.get("machines") {
+        Some(Value::Array(machines)) => machines
+            .iter()
+            .map(|machine| {
+                machine.as_u64().ok_or_else(|| ParseError::InvalidField {
+                    path: path.to_string(),
+                    index: 0,
+                    field: "machines",
+                    value: machine.to_string(),
+                })
+            })
+            .collect::<Result<Vec<u64>, ParseError>>()?,
+        _ => (0..parse_field(&value, path, 0, "machines")?).collect(),
+    };
+
+    if machines.len() as u64 > width * height {
+        return Err(ParseError::TooManyMachines {
+            path: path.to_string(),
+            machines: machines.len(),
+            cells: width * height,
+        });
+    }
+    // like the inferred dimensions, the crossover needs two rows to cut between
+    if height < 2 {
+        return Err(ParseError::Config {
+            path: path.to_string(),
+            source: ConfigError::TooFewRows { height },
+        });
+    }
+
+    Ok(Dimensions {
+        width,
+        height,
+        machines,
+        fixed_machines: Vec::new(),
+        blocked_cells: Vec::new(),
+    })
+}
+
+/// the most square grid which fits all the machines and has at least two rows for the crossover
+pub fn infer_dimensions(machines: Vec<u64>) -> Dimensions {
+    let count = machines.len().max(2) as u64;
+    let width = (count as f64).sqrt().ceil() as u64;
+    let height = count.div_ceil(width);
+
+    let (width, height) = if height < 2 {
+        (1, count)
+    } else {
+        (width, height)
+    };
+
+    Dimensions {
+        width,
+        height,
+        machines,
+        fixed_machines: Vec::new(),
+        blocked_cells: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_explicit_dimensions_with_a_single_row() {
+        let dimensions = parse_dimensions(
+            "one_row.json",
+            r#"{"width": 4, "height": 1, "machines": 3}"#,
+        );
+
+        assert!(matches!(
+            dimensions,
+            Err(ParseError::Config {
+                source: ConfigError::TooFewRows { height: 1 },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn infers_at_least_two_rows() {
+        for count in 0..20 {
+            let dimensions = infer_dimensions((0..count).collect());
+
+            assert!(dimensions.height >= 2);
+            assert!(dimensions.width * dimensions.height >= count);
+        }
+    }
+}
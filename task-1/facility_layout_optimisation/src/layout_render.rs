@@ -0,0 +1,191 @@
+use crate::distance_model::DistanceModel;
+use crate::facility::Facility;
+use crate::facility_layout::FacilityLayout;
+use std::fmt::Write;
+
+const CELL_SIZE: f64 = 64.0;
+const MARGIN: f64 = 24.0;
+const MIN_FLOW_WIDTH: f64 = 1.0;
+const MAX_FLOW_WIDTH: f64 = 14.0;
+
+/// draws the facility grid with box characters, empty cells are shown as '.'
+pub fn render_ascii(facility: &Facility) -> String {
+    let cell_width = facility
+        .find_max_machine()
+        .map(|machine| machine.to_string().len())
+        .unwrap_or(1)
+        + 2;
+    let separator = format!(
+        "+{}\n",
+        format!("{}+", "-".repeat(cell_width)).repeat(facility.get_width() as usize)
+    );
+
+    let mut ascii = separator.clone();
+    for row in 0..facility.get_height() {
+        ascii.push('|');
+        for column in 0..facility.get_width() {
+            let label = match facility.get_machine(row, column) {
+                Some(machine) => machine.to_string(),
+                None => String::from("."),
+            };
+            ascii.push_str(&format!("{:^width$}|", label, width = cell_width));
+        }
+        ascii.push('\n');
+        ascii.push_str(&separator);
+    }
+
+    ascii
+}
+
+/// the facility on a single line, the rows are separated by '/' and empty cells are shown as '.'
+pub fn render_inline(facility: &Facility) -> String {
+    let rows: Vec<String> = (0..facility.get_height())
+        .map(|row| {
+            let cells: Vec<String> = (0..facility.get_width())
+                .map(|column| match facility.get_machine(row, column) {
+                    Some(machine) => machine.to_string(),
+                    None => String::from("."),
+                })
+                .collect();
+            cells.join(" ")
+        })
+        .collect();
+
+    rows.join("/")
+}
+
+/// draws the facility grid with the flows between machines as paths,
+/// the width of a path is proportional to amount * cost of its flow
+pub fn render_svg(facility: &Facility, facility_layout: &FacilityLayout) -> String {
+    let width = facility.get_width() as f64 * CELL_SIZE + 2.0 * MARGIN;
+    let height = facility.get_height() as f64 * CELL_SIZE + 2.0 * MARGIN;
+
+    let mut svg = String::new();
+    // writing to a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        width, height
+    );
+
+    for row in 0..facility.get_height() {
+        for column in 0..facility.get_width() {
+            let fill = match facility.get_machine(row, column) {
+                Some(_) => "#dbe9f6",
+                None => "#f4f4f4",
+            };
+            let _ = writeln!(
+                svg,
+                r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" stroke="#888888"/>"##,
+                MARGIN + column as f64 * CELL_SIZE,
+                MARGIN + row as f64 * CELL_SIZE,
+                CELL_SIZE,
+                CELL_SIZE,
+                fill
+            );
+        }
+    }
+
+    write_flows(&mut svg, facility, facility_layout);
+
+    // the labels are drawn last, so that the flows do not cover them
+    for row in 0..facility.get_height() {
+        for column in 0..facility.get_width() {
+            if let Some(machine) = facility.get_machine(row, column) {
+                let (x, y) = cell_centre(row, column);
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" dominant-baseline="central" font-size="18">{}</text>"#,
+                    x, y, machine
+                );
+            }
+        }
+    }
+
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
+
+fn write_flows(svg: &mut String, facility: &Facility, facility_layout: &FacilityLayout) {
+    let max_weight = facility_layout
+        .facility_flows
+        .iter()
+        .map(|flow| flow.amount * flow.cost)
+        .fold(0.0, f64::max);
+
+    if max_weight == 0.0 {
+        return;
+    }
+
+    for flow in &facility_layout.facility_flows {
+        let weight = flow.amount * flow.cost;
+        if weight == 0.0 || flow.source == flow.dest {
+            continue;
+        }
+
+        let (from_cell, to_cell, from, to) = match (
+            facility.cell_of(flow.source),
+            facility.cell_of(flow.dest),
+            facility.locate(flow.source),
+            facility.locate(flow.dest),
+        ) {
+            (Some(from_cell), Some(to_cell), Some(from), Some(to)) => (
+                from_cell,
+                to_cell,
+                cell_centre(from.0, from.1),
+                cell_centre(to.0, to.1),
+            ),
+            _ => continue,
+        };
+
+        // the flows follow the paths the distance model measures - along the aisles for the
+        // Manhattan distance, straight for the Euclidean and Chebyshev ones, while the flows of
+        // explicit matrices are bent sideways, so that flows along a row or column stay distinguishable
+        let path = match facility_layout.distance_model {
+            DistanceModel::Manhattan => format!(
+                "M {:.1} {:.1} L {:.1} {:.1} L {:.1} {:.1}",
+                from.0, from.1, to.0, from.1, to.0, to.1
+            ),
+            DistanceModel::Euclidean | DistanceModel::Chebyshev => {
+                format!("M {:.1} {:.1} L {:.1} {:.1}", from.0, from.1, to.0, to.1)
+            }
+            DistanceModel::Matrix(_) => {
+                let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+                let bend = 0.2;
+                format!(
+                    "M {:.1} {:.1} Q {:.1} {:.1} {:.1} {:.1}",
+                    from.0,
+                    from.1,
+                    (from.0 + to.0) / 2.0 - dy * bend,
+                    (from.1 + to.1) / 2.0 + dx * bend,
+                    to.0,
+                    to.1
+                )
+            }
+        };
+        let distance =
+            facility_layout
+                .distance_model
+                .distance(facility.get_width(), from_cell, to_cell);
+        let stroke_width = MIN_FLOW_WIDTH + (MAX_FLOW_WIDTH - MIN_FLOW_WIDTH) * weight / max_weight;
+
+        let _ = writeln!(
+            svg,
+            r##"<path d="{}" fill="none" stroke="#d62728" stroke-opacity="0.45" stroke-width="{:.2}" stroke-linecap="round" stroke-linejoin="round"><title>{} - {}: amount {} x cost {} x distance {:.2}</title></path>"##,
+            path, stroke_width, flow.source, flow.dest, flow.amount, flow.cost, distance
+        );
+    }
+}
+
+fn cell_centre(row: u64, column: u64) -> (f64, f64) {
+    (
+        MARGIN + (column as f64 + 0.5) * CELL_SIZE,
+        MARGIN + (row as f64 + 0.5) * CELL_SIZE,
+    )
+}
@@ -0,0 +1,91 @@
+//! Genetic and multi-objective optimisation of facility layouts - machines are placed onto the cells
+//! of a grid so that the cost of the material flowing between them is as low as possible.
+//!
+//! An instance is a [`Dimensions`] (the grid and its machines) together with a [`FacilityLayout`]
+//! (the flows, costs and distance model), read from the JSON files of the data directory with
+//! [`flow_parser`], from archives and directories with [`instance_loader`] or from QAPLIB files
+//! with [`qaplib`]. A layout is a [`Facility`], its [`Fitness`] is its flow cost.
+//!
+//! The solvers:
+//! - [`specimen::Simulation`] - the genetic algorithm over a [`Population`] of any [`Genome`],
+//!   one generation at a time
+//! - [`nsga2::optimise_pareto_front`] - NSGA-II over several [`objective::Objective`]s
+//! - [`dynamic_layout::DynamicInstance::optimise`] - layouts for several periods with rearrangement costs
+//! - [`differential_evolution::DifferentialEvolution`] and [`particle_swarm::ParticleSwarm`] -
+//!   continuous optimisers over [`random_key::RandomKeys`]
+//! - [`ant_colony::AntColony`] - the MAX-MIN ant system, optionally with local search
+//!
+//! The statistics of the runs are written as results files, which [`run_log`] reads back,
+//! [`statistical_comparison`] compares and [`convergence_plot`] plots. While they run,
+//! [`progress`] follows them on the terminal.
+
+// TODO enable and fix all of those... Remember to run 'cargo clean' first
+//#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+/// The MAX-MIN ant system.
+pub mod ant_colony;
+/// Saving and restoring the state of an interrupted experiment.
+pub mod checkpoint;
+/// Convergence plots of results files.
+pub mod convergence_plot;
+/// Differential evolution over random-key layouts.
+pub mod differential_evolution;
+/// The distances between cells.
+pub mod distance_model;
+/// Layouts over several planning periods.
+pub mod dynamic_layout;
+/// The errors of parsing, configuration and the populations.
+pub mod error;
+/// Counting the fitness evaluations of the solvers.
+pub mod evaluator;
+/// A single layout and its genetic operators.
+pub mod facility;
+/// The grid, its machines and the placement constraints.
+pub mod facility_configuration;
+/// The flows, costs and distance model an instance is evaluated with.
+pub mod facility_layout;
+/// The fitness of the solutions.
+pub mod fitness;
+/// Irregular facility shapes.
+pub mod floor_plan;
+/// Reading the JSON files of an instance.
+pub mod flow_parser;
+/// Machines covering several cells.
+pub mod footprint;
+/// The encodings the genetic algorithm can evolve.
+pub mod genome;
+/// Random instances.
+pub mod instance_generator;
+/// Instances from zip archives and directories.
+pub mod instance_loader;
+/// ASCII and SVG rendering of layouts.
+pub mod layout_render;
+/// NSGA-II multi-objective optimisation.
+pub mod nsga2;
+/// The criteria of multi-objective optimisation.
+pub mod objective;
+/// Particle swarm optimisation over random-key layouts.
+pub mod particle_swarm;
+/// Live progress of the runs on the terminal.
+pub mod progress;
+/// The QAPLIB instance and solution formats.
+pub mod qaplib;
+/// The random-key encoding of layouts.
+pub mod random_key;
+/// Muther's closeness ratings.
+pub mod rel_chart;
+/// Reading results files back.
+pub mod run_log;
+/// Populations of layouts and the genetic algorithm.
+pub mod specimen;
+/// Statistical comparison of configurations.
+pub mod statistical_comparison;
+
+pub use error::{ConfigError, ParseError, PopulationError};
+pub use evaluator::Evaluator;
+pub use facility::{generate_randomised_facilities, Facility, LayoutContext};
+pub use facility_configuration::Dimensions;
+pub use facility_layout::FacilityLayout;
+pub use fitness::Fitness;
+pub use genome::{Genome, LayoutGenome};
+pub use specimen::{Population, Simulation, SimulationParameters, Specimen};
@@ -1,113 +1,966 @@
-// TODO enable and fix all of those... Remember to run 'cargo clean' first
-//#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
-
-use crate::facility::Facility;
-use crate::facility_configuration::{Dimensions, FacilityConfig};
-use crate::facility_layout::FacilityLayout;
-use crate::flow_parser::parse_flows;
-use crate::specimen::{Population, Specimen};
-use rand::prelude::SliceRandom;
+// the command line interface of the optimiser, see the library for the solvers themselves
+
+use facility_layout_optimisation::ant_colony::AntColony;
+use facility_layout_optimisation::checkpoint::{
+    read_checkpoint, read_checkpoint_args, ExperimentState,
+};
+use facility_layout_optimisation::convergence_plot::{render_convergence_svg, RunAggregation};
+use facility_layout_optimisation::differential_evolution::{DifferentialEvolution, Strategy};
+use facility_layout_optimisation::distance_model::DistanceModel;
+use facility_layout_optimisation::dynamic_layout::read_dynamic_instance;
+use facility_layout_optimisation::error::PopulationError;
+use facility_layout_optimisation::evaluator::Evaluator;
+use facility_layout_optimisation::facility::{
+    generate_randomised_facilities, Crossover, Facility, LayoutContext,
+};
+use facility_layout_optimisation::facility_configuration::{Dimensions, FacilityConfig};
+use facility_layout_optimisation::facility_layout::FacilityLayout;
+use facility_layout_optimisation::floor_plan::read_floor_plan;
+use facility_layout_optimisation::flow_parser::{
+    parse_constraints, parse_distance_matrix, parse_flows, parse_noise_levels,
+};
+use facility_layout_optimisation::footprint::{placed_facility, read_footprints};
+use facility_layout_optimisation::genome::LayoutGenome;
+use facility_layout_optimisation::instance_generator::{Distribution, GeneratorSettings};
+use facility_layout_optimisation::instance_loader::{
+    infer_dimensions, load_from_directory, InstanceArchive,
+};
+use facility_layout_optimisation::layout_render::{render_ascii, render_inline, render_svg};
+use facility_layout_optimisation::nsga2::optimise_pareto_front;
+use facility_layout_optimisation::objective::Objective;
+use facility_layout_optimisation::particle_swarm::ParticleSwarm;
+use facility_layout_optimisation::progress::Progress;
+use facility_layout_optimisation::qaplib::{self, QapInstance, QapSolution};
+use facility_layout_optimisation::random_key::RandomKeys;
+use facility_layout_optimisation::rel_chart::{read_closeness_ratings, RelChart};
+use facility_layout_optimisation::run_log::read_run_log;
+use facility_layout_optimisation::specimen::{
+    Population, Selection, Simulation, SimulationParameters, Specimen,
+};
+use facility_layout_optimisation::statistical_comparison::ComparisonReport;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::env;
+use std::error::Error;
 use std::fs;
 use std::fs::OpenOptions;
-use std::io::Write;
-
-mod facility;
-mod facility_configuration;
-mod facility_layout;
-mod flow_parser;
-mod specimen;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process;
 
 fn main() {
-    // let config = FacilityConfig::get_easy_config().unwrap();
-    let config = FacilityConfig::get_flat_config().unwrap();
-    // let config = FacilityConfig::get_hard_config().unwrap();
-    let population_size: u32 = 20;
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("compare") => compare_configurations(&args[1..]),
+        Some("plot") => plot_convergence(&args[1..]),
+        Some("instances") => list_instances(&args[1..]),
+        Some("qaplib") => convert_qaplib(&args[1..]),
+        Some("pareto") => optimise_multi_objective(&args[1..]),
+        Some("generate") => generate_instance(&args[1..]),
+        Some("dynamic") => optimise_dynamic_layout(&args[1..]),
+        Some("resume") => resume_experiment(&args[1..]),
+        Some("continuous") => optimise_continuous(&args[1..]),
+        Some("aco") => optimise_ant_colony(&args[1..]),
+        _ => run_experiment(&args),
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+// usage: compare [--paired] <results file> <results file> [<results file>...]
+// --paired when the i-th runs of all the configurations belong together, e.g. share their seed
+fn compare_configurations(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let paired = args.iter().any(|arg| arg == "--paired");
+    let run_logs = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(|file_name| read_run_log(file_name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    print!("{}", ComparisonReport::new(&run_logs, paired)?);
+    Ok(())
+}
+
+// usage: plot [--overlay] <results file>...
+// writes <results file>.svg next to every results file
+fn plot_convergence(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let aggregation = if args.iter().any(|arg| arg == "--overlay") {
+        RunAggregation::Overlay
+    } else {
+        RunAggregation::Average
+    };
+
+    for file_name in args.iter().filter(|arg| !arg.starts_with("--")) {
+        let run_log = read_run_log(file_name)?;
+
+        let svg_name = format!("{}.svg", file_name);
+        fs::write(&svg_name, render_convergence_svg(&run_log, &aggregation))?;
+        println!("{}", svg_name);
+    }
+
+    Ok(())
+}
+
+// usage: instances <zip archive>
+fn list_instances(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut archive = InstanceArchive::open(args.first().ok_or("no archive given")?)?;
+
+    for name in archive.instance_names() {
+        let dimensions = archive.dimensions(&name)?;
+        println!(
+            "{}: {}x{}, {} machines",
+            name,
+            dimensions.width,
+            dimensions.height,
+            dimensions.machines.len()
+        );
+    }
+
+    Ok(())
+}
+
+// usage: qaplib export <instance> <.dat file> [--archive <zip archive>] [--default-cost <cost>]
+//        qaplib evaluate <.dat file> <.sln file>
+fn convert_qaplib(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match (args.first().map(String::as_str), args.get(1), args.get(2)) {
+        (Some("export"), Some(instance), Some(dat_file)) => {
+            let (dimensions, facility_layout) = load_instance(instance, args)?;
+            qaplib::write_instance(
+                dat_file,
+                &QapInstance::from_problem(&dimensions, &facility_layout),
+            )?;
+            println!("{}", dat_file);
+        }
+        (Some("evaluate"), Some(dat_file), Some(sln_file)) => {
+            let (dimensions, facility_layout) = qaplib::read_instance(dat_file)?.into_problem();
+            let solution = qaplib::read_solution(sln_file)?;
+            if solution.permutation.len() != dimensions.machines.len() {
+                return Err("the solution and the instance sizes differ".into());
+            }
+
+            let facility = solution.to_facility(&dimensions);
+            println!("cost given in the solution: {}", solution.cost);
+            println!(
+                "calculated fitness: {}",
+                facility.calculate_fitness(&facility_layout)
+            );
+        }
+        _ => return Err(
+            "usage: qaplib export <instance> <.dat file> | qaplib evaluate <.dat file> <.sln file>"
+                .into(),
+        ),
+    }
+
+    Ok(())
+}
+
+// usage: generate <name> --machines <count> [--width <cells> --height <cells>] [--density <share>]
+//        [--amounts <distribution>] [--max-amount <amount>] [--costs <distribution>]
+//        [--max-cost <cost>] [--seed <seed>] [--output <directory>]
+// the distributions are uniform, clustered or power-law, the grid is as square as possible by default
+// the instance is written to the output directory, by default generated/, see --directory
+fn generate_instance(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let name = args
+        .first()
+        .filter(|arg| !arg.starts_with("--"))
+        .ok_or("no instance name given")?;
+    let number = |flag: &str, default: u64| -> Result<u64, Box<dyn Error>> {
+        Ok(option_value(args, flag)
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(default))
+    };
+    let distribution = |flag: &str| -> Result<Distribution, Box<dyn Error>> {
+        let name = option_value(args, flag).unwrap_or("uniform");
+        Distribution::from_name(name).ok_or_else(|| {
+            format!(
+                "unknown distribution '{}', expected uniform, clustered or power-law",
+                name
+            )
+            .into()
+        })
+    };
+
+    let machines: Vec<u64> = (0..number("--machines", 0)?).collect();
+    if machines.len() < 2 {
+        return Err("at least two machines are needed, use --machines".into());
+    }
+    let mut dimensions = infer_dimensions(machines);
+    if option_value(args, "--width").is_some() || option_value(args, "--height").is_some() {
+        dimensions.width = number("--width", dimensions.width)?;
+        dimensions.height = number("--height", dimensions.height)?;
+    }
+
+    let settings = GeneratorSettings {
+        dimensions,
+        density: option_value(args, "--density")
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(0.3),
+        amounts: distribution("--amounts")?,
+        max_amount: number("--max-amount", 100)?,
+        costs: distribution("--costs")?,
+        max_cost: number("--max-cost", 10)?,
+        seed: number("--seed", 0)?,
+    };
+    settings.validate()?;
+
+    let paths = settings.generate().write(
+        option_value(args, "--output").unwrap_or("generated"),
+        name,
+        &settings.dimensions,
+    )?;
+    for path in paths {
+        println!("{}", path);
+    }
+
+    Ok(())
+}
+
+// usage: dynamic [<instance>] --periods <.json file> [--generations <count>] [--evaluations <count>]
+//        [--seed <seed>] and the instance options of the experiment below
+// the instance gives the grid, the distances and the constraints, the periods file gives the flows
+// of every period and the rearrangement costs, see read_dynamic_instance
+// writes the statistics of every generation to dynamic_<instance>.txt, every plan is one evaluation
+fn optimise_dynamic_layout(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let instance = args
+        .first()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or("flat");
+    let budget = evaluation_budget(args)?;
+    let generations = generation_limit(args, "--generations", budget, 500)?;
+    let default_cost = default_cost(args)?;
+    let mut rng = match option_value(args, "--seed") {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed.parse()?),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let (dimensions, facility_layout) = load_instance(instance, args)?;
+
+    let periods_file = option_value(args, "--periods").ok_or("no periods given, use --periods")?;
+    let dynamic_instance = read_dynamic_instance(
+        periods_file,
+        &facility_layout,
+        &dimensions.machines,
+        default_cost,
+    )?;
+
+    let file_name = format!("dynamic_{}.txt", instance);
+    fs::write(&file_name, "best,worst,average,deviation,evaluations\n\n")?;
+
+    let context = LayoutContext::new(&dimensions, &facility_layout);
+    let plan = dynamic_instance.optimise(
+        200,
+        generations,
+        5,
+        &Evaluator::new(&context, budget),
+        &mut rng,
+        0.75,
+        0.25,
+        &file_name,
+    )?;
+
+    for (period, (layout, period_layout)) in plan
+        .layouts
+        .iter()
+        .zip(&dynamic_instance.periods)
+        .enumerate()
+    {
+        println!(
+            "period {}: flow cost {}",
+            period + 1,
+            layout.calculate_fitness(period_layout)
+        );
+        print!(
+            "{}",
+            render_ascii(&decode_footprints(layout, period_layout))
+        );
+    }
+    println!(
+        "total cost {} = flow cost {} + rearrangement cost {}",
+        plan.get_total_cost(),
+        plan.flow_cost,
+        plan.rearrangement_cost
+    );
+    println!("statistics written to {}", file_name);
+
+    Ok(())
+}
+
+// usage: pareto [<instance>] [--noise <.json file>] [--rel <.json file>] [--generations <count>]
+//        [--evaluations <count>] [--seed <seed>] and the instance options of the experiment below
+// the flow cost is always an objective, the noise separation and the closeness are the others
+// writes the non-dominated layouts and their objective vectors to pareto_<instance>.txt
+fn optimise_multi_objective(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let instance = args
+        .first()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or("flat");
+    let budget = evaluation_budget(args)?;
+    let generations = generation_limit(args, "--generations", budget, 200)?;
+    let mut rng = match option_value(args, "--seed") {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed.parse()?),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let (dimensions, facility_layout) = load_instance(instance, args)?;
+
+    let mut objectives = vec![Objective::FlowCost];
+    if let Some(noise_file) = option_value(args, "--noise") {
+        objectives.push(Objective::NoiseSeparation(parse_noise_levels(
+            noise_file,
+            &dimensions.machines,
+        )?));
+    }
+    if facility_layout.rel_chart.is_some() {
+        objectives.push(Objective::Closeness);
+    }
+    if objectives.len() < 2 {
+        return Err("a second objective is needed, use --noise and/or --rel".into());
+    }
+
+    let context = LayoutContext::new(&dimensions, &facility_layout);
+    let evaluator = Evaluator::new(&context, budget);
+    let pareto_front = optimise_pareto_front(
+        100,
+        generations,
+        &evaluator,
+        &mut rng,
+        &objectives,
+        0.75,
+        0.25,
+    )?;
+
+    let names: Vec<&str> = objectives.iter().map(Objective::get_name).collect();
+    let mut contents = format!("{},layout\n", names.join(","));
+    for specimen in &pareto_front {
+        let values: Vec<String> = specimen
+            .objectives
+            .iter()
+            .map(|value| format!("{:.3}", value))
+            .collect();
+        contents.push_str(&format!(
+            "{},{}\n",
+            values.join(","),
+            render_inline(&specimen.facility)
+        ));
+    }
+
+    let file_name = format!("pareto_{}.txt", instance);
+    fs::write(&file_name, &contents)?;
+    print!("{}", contents);
+    println!(
+        "{} non-dominated layouts found with {} evaluations written to {}",
+        pareto_front.len(),
+        evaluator.get_evaluations(),
+        file_name
+    );
+
+    Ok(())
+}
+
+// usage: continuous [<instance>] --algorithm de-rand|de-best|pso [--generations <count>]
+//        [--evaluations <count>] [--seed <seed>] and the instance options of the experiment below
+// differential evolution (rand/1/bin or best/1/bin) or particle swarm optimisation over random-key
+// layouts, run 10 times like the genetic algorithm of the experiment
+// writes the statistics of every generation to <algorithm>_<instance>.txt, in the format of the
+// experiment's results, so that both can be compared and plotted together, and the best layout
+// of all the runs to best_<algorithm>_<instance>.svg
+fn optimise_continuous(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let instance = instance_name(args);
+    let algorithm = option_value(args, "--algorithm")
+        .ok_or("no algorithm given, use --algorithm de-rand, de-best or pso")?;
+    // the differential evolution strategy, none for the particle swarm
+    let strategy = match algorithm {
+        "de-rand" => Some(Strategy::Rand1Bin),
+        "de-best" => Some(Strategy::Best1Bin),
+        "pso" => None,
+        algorithm => {
+            return Err(format!(
+                "unknown algorithm '{}', expected de-rand, de-best or pso",
+                algorithm
+            )
+            .into())
+        }
+    };
+    let budget = evaluation_budget(args)?;
+    let generations = generation_limit(args, "--generations", budget, 500)?;
+    let mut rng = match option_value(args, "--seed") {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed.parse()?),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let (dimensions, facility_layout) = load_instance(instance, args)?;
+    let context = LayoutContext::new(&dimensions, &facility_layout);
+
+    let particle_swarm = ParticleSwarm {
+        swarm_size: 100,
+        iterations: generations,
+        inertia: 0.72,
+        cognitive_weight: 1.49,
+        social_weight: 1.49,
+        max_velocity: 0.2,
+    };
+
+    let file_name = format!("{}_{}.txt", algorithm, instance);
+    repeat_runs(&file_name, &facility_layout, |file_name| {
+        let evaluator = Evaluator::new(&context, budget);
+        let specimen = match strategy {
+            Some(strategy) => DifferentialEvolution {
+                population_size: 100,
+                generations,
+                strategy,
+                differential_weight: 0.5,
+                crossover_rate: 0.9,
+            }
+            .optimise(&evaluator, &mut rng, file_name)?,
+            None => particle_swarm.optimise(&evaluator, &mut rng, file_name)?,
+        };
+
+        Ok(Specimen::new(
+            specimen.genome.decode(&dimensions),
+            specimen.fitness,
+        ))
+    })
+}
+
+// usage: aco [<instance>] [--ants <count>] [--iterations <count>] [--evaluations <count>]
+//        [--evaporation <share>] [--local-search] [--seed <seed>] and the instance options of
+//        the experiment below
+// the MAX-MIN ant system, run 10 times like the genetic algorithm of the experiment, with
+// --local-search every ant's layout is improved by swapping cells
+// writes the statistics of every iteration to aco_<instance>.txt, in the format of the
+// experiment's results, and the best layout of all the runs to best_aco_<instance>.svg
+fn optimise_ant_colony(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let instance = instance_name(args);
+    let budget = evaluation_budget(args)?;
+    let ant_colony = AntColony {
+        ants: option_value(args, "--ants")
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(20),
+        iterations: generation_limit(args, "--iterations", budget, 500)?,
+        evaporation: option_value(args, "--evaporation")
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(0.2),
+        local_search: args.iter().any(|arg| arg == "--local-search"),
+    };
+    ant_colony.validate()?;
+    let mut rng = match option_value(args, "--seed") {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed.parse()?),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let (dimensions, facility_layout) = load_instance(instance, args)?;
+    let context = LayoutContext::new(&dimensions, &facility_layout);
+
+    let file_name = format!("aco_{}.txt", instance);
+    repeat_runs(&file_name, &facility_layout, |file_name| {
+        ant_colony.optimise(&Evaluator::new(&context, budget), &mut rng, file_name)
+    })
+}
 
-    let facility_layout = parse_flows(config.get_flow_path(), config.get_cost_path());
+// starts the results file and runs the solver 10 times, each run appends its statistics to the
+// file and returns its best layout, the best of them is drawn to best_<results file>.svg
+fn repeat_runs<F>(
+    file_name: &str,
+    facility_layout: &FacilityLayout,
+    mut run: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Result<Specimen, PopulationError>,
+{
+    fs::write(file_name, "best,worst,average,deviation,evaluations\n\n")?;
 
-    let facilities = generate_randomised_facilities(&config.dimensions, population_size);
-    let population = Population::fit_facilities(facilities, &facility_layout);
+    let mut best_specimen: Option<Specimen> = None;
 
-    println!("{}", population.select_by_tournament(5).unwrap().fitness);
-    println!("{}", population.select_by_roulette().unwrap().fitness);
+    for _run in 0..10 {
+        let specimen = run(file_name)?;
+
+        let mut file = OpenOptions::new().append(true).open(file_name)?;
+        writeln!(file)?;
+
+        println!("{}", specimen.fitness);
+        print!(
+            "{}",
+            render_ascii(&decode_footprints(&specimen.genome, facility_layout))
+        );
+
+        if best_specimen
+            .as_ref()
+            .is_none_or(|best| specimen.fitness < best.fitness)
+        {
+            best_specimen = Some(specimen);
+        }
+    }
+
+    if let Some(best) = best_specimen {
+        let svg_name = format!("best_{}.svg", file_name.trim_end_matches(".txt"));
+        fs::write(
+            &svg_name,
+            render_svg(
+                &decode_footprints(&best.genome, facility_layout),
+                facility_layout,
+            ),
+        )?;
+        println!("best layout ({}) written to {}", best.fitness, svg_name);
+    }
+    println!("statistics written to {}", file_name);
+
+    Ok(())
+}
+
+// usage: [<instance>] [--archive <zip archive> | --directory <directory> | --qaplib <.dat file>]
+//        [--default-cost <cost>]
+//        [--distance manhattan|euclidean|chebyshev|<.json matrix>] [--constraints <.json file>]
+//        [--floor-plan <.txt or .json map>] [--footprints <.json file>]
+//        [--rel <.json file> [--flow-weight <weight>] [--closeness-weight <weight>]]
+//        [--encoding grid|random-keys] [--crossover row|block] [--generations <count>] [--evaluations <count>]
+//        [--seed <seed>] [--checkpoint <file> [--checkpoint-every <generations>]] [--no-progress]
+// without an archive or a directory, the instance is one of easy, flat or hard from the data directory
+// the constraints fix machines onto cells and block cells, see parse_constraints
+// the floor plan gives the shape of the facility, see FloorPlan
+// the footprints give machines several cells, see read_footprints and Footprints::place
+// the REL chart adds the closeness penalty to the fitness, both weights default to 1
+// the layouts are evolved as grids by default, or as random keys with --encoding random-keys
+// the grids are crossed over by rows by default, or by rectangles with --crossover block
+// every run ends after --generations (500 by default) or once it used --evaluations fitness
+// evaluations, the results have a column with the evaluations so far, see Evaluator
+// the same --seed always gives the same runs, a random one is used without it
+// with --checkpoint, the state of the experiment is written to the file every --checkpoint-every
+// generations (50 by default), see resume_experiment
+// on a terminal, the progress of the runs is shown on stderr, --no-progress turns it off
+fn run_experiment(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let encoding = parse_encoding(args)?;
+    parse_crossover(args)?;
+    let instance = instance_name(args);
+    let (dimensions, facility_layout) = load_instance(instance, args)?;
+    let mut rng = match option_value(args, "--seed") {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed.parse()?),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let population_size: u32 = 20;
+
+    let facilities = generate_randomised_facilities(&dimensions, population_size, &mut rng);
+    let context = LayoutContext::new(&dimensions, &facility_layout);
+    let population = Population::fit(facilities, &Evaluator::new(&context, None));
+
+    println!("{}", population.select_by_tournament(5, &mut rng)?.fitness);
+    println!("{}", population.select_by_roulette(&mut rng)?.fitness);
 
     println!("---------- crossover: ----------");
-    test_crossover();
+    test_crossover(&mut rng);
 
     println!("---------- mutation: ----------");
-    test_mutation();
+    test_mutation(&mut rng);
 
     println!("-------- tournament: --------");
-    // let specialised_tournament = |tournament_size| {
-    //     move |population| Population::select_by_tournament(population, tournament_size)
-    // };
-    let specialised_tournament: fn(&Population) -> Result<&Specimen, &'static str> =
-        |population| Population::select_by_tournament(population, 5);
+    fs::write(
+        format!("tournament_{}.txt", instance),
+        "best,worst,average,deviation,evaluations\n\n",
+    )?;
+
+    match encoding {
+        Encoding::Grid => run_simulations::<Facility>(
+            ExperimentState::new(args.to_vec(), rng),
+            &dimensions,
+            &facility_layout,
+        ),
+        Encoding::RandomKeys => run_simulations::<RandomKeys>(
+            ExperimentState::new(args.to_vec(), rng),
+            &dimensions,
+            &facility_layout,
+        ),
+    }
+}
+
+// usage: resume <checkpoint>
+// continues an experiment from its last checkpoint with the arguments it was started with,
+// the results file is restored to the checkpoint first, so it ends up the same as after
+// an uninterrupted run with the same seed
+fn resume_experiment(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = args.first().ok_or("usage: resume <checkpoint>")?;
+
+    match parse_encoding(&read_checkpoint_args(path)?)? {
+        Encoding::Grid => resume_simulations::<Facility>(path),
+        Encoding::RandomKeys => resume_simulations::<RandomKeys>(path),
+    }
+}
 
-    let random_selection: fn(&Population) -> Result<&Specimen, &'static str> = |population| {
-        population
-            .specimens
-            .choose(&mut rand::thread_rng())
-            .ok_or("TODO")
+fn resume_simulations<G: LayoutGenome>(path: &str) -> Result<(), Box<dyn Error>> {
+    let (state, statistics) = read_checkpoint::<G>(path)?;
+
+    let instance = instance_name(&state.args);
+    let (dimensions, facility_layout) = load_instance(instance, &state.args)?;
+    fs::write(format!("tournament_{}.txt", instance), statistics)?;
+
+    println!(
+        "resuming run {} at generation {}",
+        state.run + 1,
+        state
+            .simulation
+            .as_ref()
+            .map_or(0, |simulation| simulation.generation)
+    );
+
+    run_simulations(state, &dimensions, &facility_layout)
+}
+
+// the runs of the genetic algorithm from the state's run onwards
+fn run_simulations<G: LayoutGenome>(
+    mut state: ExperimentState<G>,
+    dimensions: &Dimensions,
+    facility_layout: &FacilityLayout,
+) -> Result<(), Box<dyn Error>> {
+    let args = state.args.clone();
+    let instance = instance_name(&args);
+    let file_name = &format!("tournament_{}.txt", instance);
+    let checkpoint_file = option_value(&args, "--checkpoint");
+    let checkpoint_interval: u32 = match option_value(&args, "--checkpoint-every") {
+        Some(interval) => interval.parse()?,
+        None => 50,
     };
 
-    let file_name = "tournament_flat_2.txt";
+    let budget = evaluation_budget(&args)?;
+    let mut context = LayoutContext::new(dimensions, facility_layout);
+    context.crossover = parse_crossover(&args)?;
+    let parameters = SimulationParameters {
+        population_size: 1000,
+        generations: generation_limit(&args, "--generations", budget, 500)?,
+        selection: Selection::Tournament(5),
+        crossover_factor: 0.75,
+        mutation_factor: 0.25,
+    };
+    let mut progress = (io::stderr().is_terminal()
+        && !args.iter().any(|arg| arg == "--no-progress"))
+    .then(|| Progress::new(10, parameters.generations, budget));
 
-    let mut file = OpenOptions::new().append(true).open(file_name).unwrap();
+    while state.run < 10 {
+        let evaluator = Evaluator::resume(&context, budget, state.evaluations);
 
-    fs::write(file_name, "best,worst,average,deviation\n\n").expect("Unable to write file");
+        if state.simulation.is_none() {
+            state.simulation = Some(Simulation::new(
+                parameters.clone(),
+                &evaluator,
+                &mut state.rng,
+            ));
+        }
 
-    for _i in 0..10 {
-        println!(
-            "{:?}",
-            Population::simulate_tournament(
-                1000,
-                &config.dimensions,
-                &facility_layout,
-                specialised_tournament,
-                0.75,
-                0.25,
-                500,
-                file_name
-            )
+        let specimen = loop {
+            let generation = state
+                .simulation
+                .as_ref()
+                .map_or(0, |simulation| simulation.generation);
+            if let Some(path) = checkpoint_file {
+                if checkpoint_interval > 0 && generation.is_multiple_of(checkpoint_interval) {
+                    state.evaluations = evaluator.get_evaluations();
+                    state.write_checkpoint(path, &fs::read_to_string(file_name)?)?;
+                }
+            }
+
+            if let Some(simulation) = state.simulation.as_mut() {
+                if let Some(progress) = progress.as_mut() {
+                    progress.update(
+                        state.run,
+                        simulation.generation,
+                        &simulation.population,
+                        evaluator.get_evaluations(),
+                    );
+                }
+
+                if let Some(best) = simulation.step(&evaluator, &mut state.rng, file_name)? {
+                    break best;
+                }
+            }
+        };
+
+        if let Some(progress) = progress.as_mut() {
+            progress.finish_run(evaluator.get_evaluations());
+        }
+
+        let mut file = OpenOptions::new().append(true).open(file_name)?;
+        writeln!(file)?;
+
+        let specimen = Specimen::new(specimen.genome.to_facility(dimensions), specimen.fitness);
+
+        println!("{}", specimen.fitness);
+        print!(
+            "{}",
+            render_ascii(&decode_footprints(&specimen.genome, facility_layout))
         );
 
-        writeln!(file).expect("Unable to write file");
+        if state
+            .best
+            .as_ref()
+            .is_none_or(|best| specimen.fitness < best.fitness)
+        {
+            state.best = Some(specimen);
+        }
+
+        state.simulation = None;
+        state.evaluations = 0;
+        state.run += 1;
+    }
+
+    if let Some(path) = checkpoint_file {
+        state.write_checkpoint(path, &fs::read_to_string(file_name)?)?;
+    }
+
+    if let Some(best) = state.best {
+        let svg_name = format!("best_{}.svg", instance);
+        fs::write(
+            &svg_name,
+            render_svg(
+                &decode_footprints(&best.genome, facility_layout),
+                facility_layout,
+            ),
+        )?;
+        println!("best layout ({}) written to {}", best.fitness, svg_name);
+
+        if option_value(&args, "--qaplib").is_some() {
+            let sln_name = format!("best_{}.sln", instance);
+            qaplib::write_solution(
+                &sln_name,
+                // the instance comes from QAPLIB, so its costs are whole numbers
+                &QapSolution::from_facility(
+                    &best.genome,
+                    best.fitness.value().round() as u64,
+                    dimensions,
+                ),
+            )?;
+            println!("QAPLIB solution written to {}", sln_name);
+        }
     }
+
+    Ok(())
+}
+
+// the --default-cost of the flows without a cost entry, it may be fractional but not negative
+fn default_cost(args: &[String]) -> Result<Option<f64>, Box<dyn Error>> {
+    match option_value(args, "--default-cost")
+        .map(str::parse::<f64>)
+        .transpose()?
+    {
+        Some(cost) if !(cost.is_finite() && cost >= 0.0) => {
+            Err(format!("invalid default cost {}", cost).into())
+        }
+        cost => Ok(cost),
+    }
+}
+
+// the --evaluations budget of every run
+fn evaluation_budget(args: &[String]) -> Result<Option<u64>, Box<dyn Error>> {
+    Ok(option_value(args, "--evaluations")
+        .map(str::parse)
+        .transpose()?)
+}
+
+// the generations given by the flag or the default ones, with an evaluation budget the runs
+// only end with the budget unless the generations are given as well
+fn generation_limit(
+    args: &[String],
+    flag: &str,
+    budget: Option<u64>,
+    default: u32,
+) -> Result<u32, Box<dyn Error>> {
+    match option_value(args, flag) {
+        Some(generations) => Ok(generations.parse()?),
+        None if budget.is_some() => Ok(u32::MAX),
+        None => Ok(default),
+    }
+}
+
+// how the genetic algorithm encodes the layouts
+enum Encoding {
+    // the facility itself, see Facility::crossover and Facility::mutate
+    Grid,
+    // see RandomKeys
+    RandomKeys,
+}
+
+fn parse_encoding(args: &[String]) -> Result<Encoding, Box<dyn Error>> {
+    match option_value(args, "--encoding") {
+        None | Some("grid") => Ok(Encoding::Grid),
+        Some("random-keys") => Ok(Encoding::RandomKeys),
+        Some(encoding) => Err(format!(
+            "unknown encoding '{}', expected grid or random-keys",
+            encoding
+        )
+        .into()),
+    }
+}
+
+fn parse_crossover(args: &[String]) -> Result<Crossover, Box<dyn Error>> {
+    match option_value(args, "--crossover") {
+        None | Some("row") => Ok(Crossover::Row),
+        Some("block") => Ok(Crossover::Block),
+        Some(crossover) => {
+            Err(format!("unknown crossover '{}', expected row or block", crossover).into())
+        }
+    }
+}
+
+// the instance is the first argument, or the name of the QAPLIB file, flat by default
+fn instance_name(args: &[String]) -> &str {
+    args.first()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .or_else(|| {
+            option_value(args, "--qaplib").and_then(|path| Path::new(path).file_stem()?.to_str())
+        })
+        .unwrap_or("flat")
+}
+
+// loads the named instance from the --archive zip if one is given, from the data directory otherwise
+fn load_instance(
+    instance: &str,
+    args: &[String],
+) -> Result<(Dimensions, FacilityLayout), Box<dyn Error>> {
+    let default_cost = default_cost(args)?;
+
+    let (mut dimensions, mut facility_layout) =
+        if let Some(qaplib_file) = option_value(args, "--qaplib") {
+            qaplib::read_instance(qaplib_file)?.into_problem()
+        } else if let Some(archive) = option_value(args, "--archive") {
+            let instance = InstanceArchive::open(archive)?.load(instance, default_cost)?;
+            (instance.dimensions, instance.facility_layout)
+        } else if let Some(directory) = option_value(args, "--directory") {
+            let instance = load_from_directory(directory, instance, default_cost)?;
+            (instance.dimensions, instance.facility_layout)
+        } else {
+            let config = match instance {
+                "easy" => FacilityConfig::get_easy_config(),
+                "hard" => FacilityConfig::get_hard_config(),
+                _ => FacilityConfig::get_flat_config(),
+            }?;
+
+            let facility_layout = parse_flows(
+                config.get_flow_path(),
+                config.get_cost_path(),
+                &config.dimensions.machines,
+                default_cost,
+            )?;
+
+            (config.dimensions, facility_layout)
+        };
+
+    // the floor plan replaces the rectangle, its walls are blocked and the distances are walked
+    if let Some(floor_plan) = option_value(args, "--floor-plan") {
+        let floor_plan = read_floor_plan(floor_plan)?;
+        dimensions.width = floor_plan.width;
+        dimensions.height = floor_plan.height;
+        dimensions.blocked_cells = floor_plan.get_wall_cells();
+        facility_layout.distance_model = DistanceModel::Matrix(floor_plan.walking_distances());
+    }
+
+    // either the name of a grid metric or a JSON file with the cell-to-cell distances
+    if let Some(distance) = option_value(args, "--distance") {
+        facility_layout.distance_model = match DistanceModel::from_name(distance) {
+            Some(distance_model) => distance_model,
+            None => DistanceModel::Matrix(parse_distance_matrix(
+                distance,
+                (dimensions.width * dimensions.height) as usize,
+            )?),
+        };
+    }
+
+    if let Some(constraints) = option_value(args, "--constraints") {
+        let (fixed_machines, blocked_cells) = parse_constraints(constraints, dimensions.width)?;
+        dimensions.fixed_machines = fixed_machines;
+        dimensions.blocked_cells.extend(blocked_cells);
+    }
+
+    dimensions.validate()?;
+
+    if let Some(footprints) = option_value(args, "--footprints") {
+        let footprints = read_footprints(
+            footprints,
+            dimensions.blocked_cells.clone(),
+            dimensions.fixed_machines.clone(),
+        )?;
+        footprints.validate(
+            &dimensions.machines,
+            dimensions.get_free_cells().len() + dimensions.fixed_machines.len(),
+        )?;
+        facility_layout.footprints = Some(footprints);
+    }
+
+    if let Some(rel_file) = option_value(args, "--rel") {
+        let weight = |flag: &str| -> Result<f64, Box<dyn Error>> {
+            match option_value(args, flag)
+                .map(str::parse::<f64>)
+                .transpose()?
+            {
+                Some(weight) if weight < 0.0 => {
+                    Err(format!("{} must not be negative", flag).into())
+                }
+                weight => Ok(weight.unwrap_or(1.0)),
+            }
+        };
+
+        facility_layout.rel_chart = Some(RelChart {
+            ratings: read_closeness_ratings(rel_file, &dimensions.machines)?,
+            flow_weight: weight("--flow-weight")?,
+            closeness_weight: weight("--closeness-weight")?,
+        });
+    }
+
+    Ok((dimensions, facility_layout))
 }
 
-fn generate_randomised_facilities(dimensions: &Dimensions, population_size: u32) -> Vec<Facility> {
-    (0..population_size)
-        .map(|_x| Facility::generate_randomised_facility(dimensions))
-        .collect()
+// the value following the given flag, e.g. option_value(args, "--seed") for "--seed 42"
+fn option_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
+
+// the facility with every machine covering its whole footprint, for rendering
+fn decode_footprints(facility: &Facility, facility_layout: &FacilityLayout) -> Facility {
+    match facility_layout
+        .footprints
+        .as_ref()
+        .and_then(|footprints| footprints.place(facility))
+    {
+        Some(placement) => placed_facility(&placement, facility.get_width(), facility.get_height()),
+        None => facility.clone(),
+    }
 }
 
-fn test_crossover() {
+fn test_crossover<R: Rng + ?Sized>(rng: &mut R) {
     let test_dimensions = Dimensions {
         width: 3,
         height: 3,
         machines: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
+        fixed_machines: Vec::new(),
+        blocked_cells: Vec::new(),
     };
 
-    let test_facilities = generate_randomised_facilities(&test_dimensions, 2);
-    let crossover = test_facilities[0].crossover(&test_facilities[1]);
+    let test_facilities = generate_randomised_facilities(&test_dimensions, 2, rng);
+    let crossover = test_facilities[0].crossover(&test_facilities[1], &test_dimensions, rng);
 
-    println!("first: {:?}", test_facilities[0]);
-    println!("second: {:?}", test_facilities[1]);
-    println!("first crossover: {:?}", crossover.0);
-    println!("second crossover: {:?}", crossover.1);
+    println!("first:\n{}", render_ascii(&test_facilities[0]));
+    println!("second:\n{}", render_ascii(&test_facilities[1]));
+    println!("first crossover:\n{}", render_ascii(&crossover.0));
+    println!("second crossover:\n{}", render_ascii(&crossover.1));
 }
 
-fn test_mutation() {
+fn test_mutation<R: Rng + ?Sized>(rng: &mut R) {
     let test_dimensions = Dimensions {
         width: 3,
         height: 3,
         machines: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
+        fixed_machines: Vec::new(),
+        blocked_cells: Vec::new(),
     };
 
-    let mut test_facilities = generate_randomised_facilities(&test_dimensions, 1);
-    println!("before mutation: {:?}", test_facilities[0]);
+    let mut test_facilities = generate_randomised_facilities(&test_dimensions, 1, rng);
+    println!("before mutation:\n{}", render_ascii(&test_facilities[0]));
 
-    test_facilities[0].mutate(0.15, 8);
-    println!("after mutation: {:?}", test_facilities[0]);
+    test_facilities[0].mutate(0.15, 8, &test_dimensions, rng);
+    println!("after mutation:\n{}", render_ascii(&test_facilities[0]));
 }
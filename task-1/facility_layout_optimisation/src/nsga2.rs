@@ -0,0 +1,275 @@
+use crate::error::PopulationError;
+use crate::evaluator::Evaluator;
+use crate::facility::{generate_randomised_facilities, Facility};
+use crate::objective::{evaluate_objectives, Objective};
+
+use rand::Rng;
+use std::cmp::Ordering;
+
+#[derive(Clone, Debug)]
+pub struct ParetoSpecimen {
+    pub facility: Facility,
+    pub objectives: Vec<f64>,
+    // the index of the non-dominated front the specimen belongs to, 0 is the pareto front
+    rank: usize,
+    // how far apart the specimen is from its neighbours on its front, the boundaries are infinite
+    crowding_distance: f64,
+}
+
+impl ParetoSpecimen {
+    fn new(facility: Facility, evaluator: &Evaluator<Facility>, objectives: &[Objective]) -> Self {
+        let objective_values = evaluator.evaluate_with(|context| {
+            evaluate_objectives(&facility, context.facility_layout, objectives)
+        });
+
+        ParetoSpecimen {
+            facility,
+            objectives: objective_values,
+            rank: 0,
+            crowding_distance: 0.0,
+        }
+    }
+
+    // at least as good in every objective and strictly better in one
+    fn dominates(&self, other: &ParetoSpecimen) -> bool {
+        let pairs = || self.objectives.iter().zip(&other.objectives);
+
+        pairs().all(|(mine, theirs)| mine <= theirs) && pairs().any(|(mine, theirs)| mine < theirs)
+    }
+
+    // the lower rank wins, the less crowded specimen breaks the tie
+    fn compare_crowded(&self, other: &ParetoSpecimen) -> Ordering {
+        self.rank.cmp(&other.rank).then(
+            other
+                .crowding_distance
+                .partial_cmp(&self.crowding_distance)
+                .unwrap_or(Ordering::Equal),
+        )
+    }
+}
+
+/// NSGA-II - the offspring of binary tournaments are merged with their parents and the next
+/// generation is filled front by front, the last front which does not fit is cut by crowding distance
+/// returns the non-dominated layouts of the last generation, without duplicate objective vectors
+/// the objective vector of a layout counts as a single evaluation, the search also ends once
+/// the budget of the evaluator is spent
+pub fn optimise_pareto_front<R: Rng + ?Sized>(
+    population_size: u32,
+    generations: u32,
+    evaluator: &Evaluator<Facility>,
+    rng: &mut R,
+    objectives: &[Objective],
+    crossover_factor: f64,
+    mutation_factor: f64,
+) -> Result<Vec<ParetoSpecimen>, PopulationError> {
+    let dimensions = evaluator.context.dimensions;
+    let mut population: Vec<ParetoSpecimen> =
+        generate_randomised_facilities(dimensions, population_size, rng)
+            .into_iter()
+            .map(|facility| ParetoSpecimen::new(facility, evaluator, objectives))
+            .collect();
+
+    let max_machine = *population
+        .first()
+        .and_then(|specimen| specimen.facility.find_max_machine())
+        .ok_or(PopulationError::EmptyPopulation)?;
+
+    assign_fronts(&mut population);
+
+    for _generation in 0..generations {
+        if evaluator.is_exhausted() {
+            break;
+        }
+
+        let mut offspring: Vec<ParetoSpecimen> = Vec::new();
+
+        while offspring.len() < population.len() {
+            let first = select_by_binary_tournament(&population, rng);
+            let second = select_by_binary_tournament(&population, rng);
+
+            let (mut first_child, mut second_child) = if rng.gen_bool(crossover_factor) {
+                first.facility.crossover(&second.facility, dimensions, rng)
+            } else {
+                (first.facility.clone(), second.facility.clone())
+            };
+
+            for child in [&mut first_child, &mut second_child] {
+                child.mutate(mutation_factor, max_machine, dimensions, rng);
+            }
+
+            offspring.push(ParetoSpecimen::new(first_child, evaluator, objectives));
+            offspring.push(ParetoSpecimen::new(second_child, evaluator, objectives));
+        }
+
+        population.append(&mut offspring);
+        assign_fronts(&mut population);
+
+        population.sort_by(ParetoSpecimen::compare_crowded);
+        population.truncate(population_size as usize);
+    }
+
+    let mut pareto_front: Vec<ParetoSpecimen> = population
+        .into_iter()
+        .filter(|specimen| specimen.rank == 0)
+        .collect();
+
+    pareto_front.sort_by(|first, second| {
+        first
+            .objectives
+            .partial_cmp(&second.objectives)
+            .unwrap_or(Ordering::Equal)
+    });
+    pareto_front.dedup_by(|first, second| first.objectives == second.objectives);
+
+    Ok(pareto_front)
+}
+
+fn select_by_binary_tournament<'a, R: Rng + ?Sized>(
+    population: &'a [ParetoSpecimen],
+    rng: &mut R,
+) -> &'a ParetoSpecimen {
+    let first = &population[rng.gen_range(0..population.len())];
+    let second = &population[rng.gen_range(0..population.len())];
+
+    match first.compare_crowded(second) {
+        Ordering::Greater => second,
+        _ => first,
+    }
+}
+
+// the fast non-dominated sort, followed by the crowding distances within every front
+fn assign_fronts(population: &mut [ParetoSpecimen]) {
+    let size = population.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![Vec::new(); size];
+    let mut domination_count: Vec<usize> = vec![0; size];
+
+    for first in 0..size {
+        for second in (first + 1)..size {
+            if population[first].dominates(&population[second]) {
+                dominated_by[first].push(second);
+                domination_count[second] += 1;
+            } else if population[second].dominates(&population[first]) {
+                dominated_by[second].push(first);
+                domination_count[first] += 1;
+            }
+        }
+    }
+
+    let mut front: Vec<usize> = (0..size)
+        .filter(|specimen| domination_count[*specimen] == 0)
+        .collect();
+    let mut rank = 0;
+
+    while !front.is_empty() {
+        let mut next_front: Vec<usize> = Vec::new();
+
+        for &specimen in &front {
+            population[specimen].rank = rank;
+
+            for &dominated in &dominated_by[specimen] {
+                domination_count[dominated] -= 1;
+                if domination_count[dominated] == 0 {
+                    next_front.push(dominated);
+                }
+            }
+        }
+
+        assign_crowding_distances(population, &front);
+        front = next_front;
+        rank += 1;
+    }
+}
+
+fn assign_crowding_distances(population: &mut [ParetoSpecimen], front: &[usize]) {
+    for &specimen in front {
+        population[specimen].crowding_distance = 0.0;
+    }
+
+    let objective_count = population[front[0]].objectives.len();
+    for objective in 0..objective_count {
+        let mut sorted: Vec<usize> = front.to_vec();
+        sorted.sort_by(|first, second| {
+            population[*first].objectives[objective]
+                .partial_cmp(&population[*second].objectives[objective])
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let values: Vec<f64> = sorted
+            .iter()
+            .map(|specimen| population[*specimen].objectives[objective])
+            .collect();
+        let range = values[values.len() - 1] - values[0];
+
+        population[sorted[0]].crowding_distance = f64::INFINITY;
+        population[sorted[sorted.len() - 1]].crowding_distance = f64::INFINITY;
+
+        if range <= 0.0 {
+            continue;
+        }
+
+        for i in 1..(sorted.len() - 1) {
+            population[sorted[i]].crowding_distance += (values[i + 1] - values[i - 1]) / range;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceModel;
+    use crate::facility::LayoutContext;
+    use crate::facility_configuration::Dimensions;
+    use crate::facility_layout::{FacilityFlow, FacilityLayout};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn reproduces_the_front_of_a_seed() {
+        let dimensions = Dimensions {
+            width: 3,
+            height: 3,
+            machines: (0..6).collect(),
+            fixed_machines: Vec::new(),
+            blocked_cells: Vec::new(),
+        };
+        let facility_layout = FacilityLayout {
+            facility_flows: (1..6)
+                .map(|dest| FacilityFlow {
+                    source: 0,
+                    dest,
+                    amount: dest as f64,
+                    cost: 1.0,
+                })
+                .collect(),
+            distance_model: DistanceModel::Manhattan,
+            footprints: None,
+            rel_chart: None,
+        };
+        let objectives = [
+            Objective::FlowCost,
+            Objective::NoiseSeparation(vec![(0, 3), (4, 2), (5, 4)]),
+        ];
+        let context = LayoutContext::new(&dimensions, &facility_layout);
+
+        let fronts: Vec<Vec<Vec<f64>>> = (0..2)
+            .map(|_run| {
+                optimise_pareto_front(
+                    20,
+                    10,
+                    &Evaluator::new(&context, None),
+                    &mut ChaCha8Rng::seed_from_u64(7),
+                    &objectives,
+                    0.75,
+                    0.25,
+                )
+                .unwrap()
+                .into_iter()
+                .map(|specimen| specimen.objectives)
+                .collect()
+            })
+            .collect();
+
+        assert!(!fronts[0].is_empty());
+        assert_eq!(fronts[0], fronts[1]);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::facility::Facility;
+use crate::facility_layout::FacilityLayout;
+
+use std::collections::HashMap;
+
+/// a criterion a layout is judged by, lower values are always better
+pub enum Objective {
+    /// the material handling cost, without the closeness penalty of the REL chart
+    FlowCost,
+    /// (machine, noise level) pairs, the closer two noisy machines are the worse
+    NoiseSeparation(Vec<(u64, u64)>),
+    /// the penalty of the layout's REL chart, its weights are ignored
+    Closeness,
+}
+
+impl Objective {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Objective::FlowCost => "flow_cost",
+            Objective::NoiseSeparation(_) => "noise",
+            Objective::Closeness => "closeness",
+        }
+    }
+
+    pub fn evaluate(&self, facility: &Facility, facility_layout: &FacilityLayout) -> f64 {
+        match self {
+            Objective::FlowCost => facility.calculate_flow_cost(facility_layout),
+            Objective::NoiseSeparation(noise_levels) => {
+                noise_exposure(facility, facility_layout, noise_levels)
+            }
+            Objective::Closeness => facility_layout
+                .rel_chart
+                .as_ref()
+                .map(|rel_chart| rel_chart.penalty(facility, facility_layout.footprints.as_ref()))
+                .unwrap_or(0.0),
+        }
+    }
+}
+
+/// the objective vector of a facility, in the order of the objectives
+pub fn evaluate_objectives(
+    facility: &Facility,
+    facility_layout: &FacilityLayout,
+    objectives: &[Objective],
+) -> Vec<f64> {
+    objectives
+        .iter()
+        .map(|objective| objective.evaluate(facility, facility_layout))
+        .collect()
+}
+
+// every pair of noisy machines adds the product of their levels, damped by the distance between them
+// the distance is offset by one, so that the pairs on cells with no distance stay finite
+// with footprints the machines are as far apart as their anchor points like for the flow cost,
+// a facility which cannot be decoded counts as if all the noisy machines stood on the same spot
+fn noise_exposure(
+    facility: &Facility,
+    facility_layout: &FacilityLayout,
+    noise_levels: &[(u64, u64)],
+) -> f64 {
+    let distance_model = &facility_layout.distance_model;
+    let width = facility.get_width();
+    let anchors: Option<HashMap<u64, (f64, f64)>> =
+        facility_layout.footprints.as_ref().map(|footprints| {
+            footprints
+                .place(facility)
+                .iter()
+                .flatten()
+                .map(|placed| (placed.machine, footprints.anchor_point(placed)))
+                .collect()
+        });
+    let mut exposure = 0.0;
+
+    for (i, (first, first_level)) in noise_levels.iter().enumerate() {
+        for (second, second_level) in &noise_levels[(i + 1)..] {
+            let distance = match &anchors {
+                Some(anchors) => match (anchors.get(first), anchors.get(second)) {
+                    (Some(from), Some(to)) => distance_model.point_distance(width, *from, *to),
+                    _ => 0.0,
+                },
+                None => match (facility.cell_of(*first), facility.cell_of(*second)) {
+                    (Some(from), Some(to)) => distance_model.distance(width, from, to),
+                    _ => continue,
+                },
+            };
+
+            exposure += (first_level * second_level) as f64 / (1.0 + distance);
+        }
+    }
+
+    exposure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceModel;
+    use crate::footprint::{Anchor, Footprints, MachineFootprint};
+
+    // machine 1 is two cells wide, its centroid is half a cell closer to machine 2 than its cell
+    #[test]
+    fn measures_the_noise_between_the_footprints() {
+        let mut facility_layout = FacilityLayout {
+            facility_flows: Vec::new(),
+            distance_model: DistanceModel::Manhattan,
+            footprints: None,
+            rel_chart: None,
+        };
+        let noise = Objective::NoiseSeparation(vec![(1, 5), (2, 5)]);
+        let facility = Facility::new(vec![Some(1), None, Some(2), None, None, None], 3);
+
+        assert_eq!(noise.evaluate(&facility, &facility_layout), 25.0 / 3.0);
+
+        facility_layout.footprints = Some(Footprints {
+            machines: HashMap::from([(
+                1,
+                MachineFootprint {
+                    width: 2,
+                    height: 1,
+                    rotatable: false,
+                    io_cell: None,
+                },
+            )]),
+            anchor: Anchor::Centroid,
+            blocked_cells: Vec::new(),
+            fixed_machines: Vec::new(),
+        });
+        assert_eq!(noise.evaluate(&facility, &facility_layout), 25.0 / 2.5);
+
+        // no two free cells are next to each other anymore for machine 1
+        let unplaceable = Facility::new(vec![Some(2), None, Some(3), None, Some(4), Some(1)], 3);
+        assert_eq!(noise.evaluate(&unplaceable, &facility_layout), 25.0);
+    }
+}
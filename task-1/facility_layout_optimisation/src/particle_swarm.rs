@@ -0,0 +1,98 @@
+use crate::error::PopulationError;
+use crate::evaluator::Evaluator;
+use crate::random_key::RandomKeys;
+use crate::specimen::{Population, Specimen};
+
+use rand::Rng;
+
+/// a global-best particle swarm, the positions of the particles are random-key layouts
+#[derive(Clone, Debug)]
+pub struct ParticleSwarm {
+    pub swarm_size: u32,
+    pub iterations: u32,
+    /// how much of its velocity a particle keeps
+    pub inertia: f64,
+    /// the pull towards the best position of the particle itself
+    pub cognitive_weight: f64,
+    /// the pull towards the best position of the whole swarm
+    pub social_weight: f64,
+    /// the largest change of a key in one iteration, the keys start between 0 and 1
+    pub max_velocity: f64,
+}
+
+impl ParticleSwarm {
+    /// moves the swarm until the last iteration or the end of the evaluation budget and returns
+    /// the best position ever found
+    /// the statistics of every iteration are appended to the file like by Simulation::step,
+    /// they are those of the best positions of the particles, as the current ones keep moving
+    pub fn optimise<R: Rng + ?Sized>(
+        &self,
+        evaluator: &Evaluator<RandomKeys>,
+        rng: &mut R,
+        file_name: &str,
+    ) -> Result<Specimen<RandomKeys>, PopulationError> {
+        let mut positions: Population<RandomKeys> =
+            Population::random(self.swarm_size, evaluator, rng);
+        let mut velocities: Vec<Vec<f64>> = positions
+            .specimens
+            .iter()
+            .map(|specimen| {
+                (0..specimen.genome.get_keys().len())
+                    .map(|_x| rng.gen_range(-self.max_velocity..=self.max_velocity))
+                    .collect()
+            })
+            .collect();
+        let mut personal_bests: Population<RandomKeys> = Population {
+            specimens: positions.specimens.clone(),
+        };
+        let mut global_best: Specimen<RandomKeys> = positions.get_best()?.clone();
+
+        for iteration in 0..=self.iterations {
+            personal_bests.write_statistics(file_name, evaluator.get_evaluations())?;
+
+            if iteration == self.iterations || evaluator.is_exhausted() {
+                break;
+            }
+
+            for ((position, velocity), personal_best) in positions
+                .specimens
+                .iter_mut()
+                .zip(&mut velocities)
+                .zip(&mut personal_bests.specimens)
+            {
+                let keys: Vec<f64> = position
+                    .genome
+                    .get_keys()
+                    .iter()
+                    .zip(velocity.iter_mut())
+                    .zip(personal_best.genome.get_keys())
+                    .zip(global_best.genome.get_keys())
+                    .map(|(((key, speed), own_best), swarm_best)| {
+                        *speed = (self.inertia * *speed
+                            + self.cognitive_weight * rng.gen::<f64>() * (own_best - key)
+                            + self.social_weight * rng.gen::<f64>() * (swarm_best - key))
+                            .clamp(-self.max_velocity, self.max_velocity);
+
+                        key + *speed
+                    })
+                    .collect();
+
+                let genome = RandomKeys::new(keys);
+                let fitness = evaluator.evaluate(&genome);
+                *position = Specimen::new(genome, fitness);
+
+                if position.fitness < personal_best.fitness {
+                    *personal_best = position.clone();
+                }
+            }
+
+            // the swarm learns about the improvements once all of its particles have moved
+            let best = personal_bests.get_best()?;
+            if best.fitness < global_best.fitness {
+                global_best = best.clone();
+            }
+        }
+
+        Ok(global_best)
+    }
+}
@@ -0,0 +1,176 @@
+use crate::specimen::Population;
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// the levels of the sparkline, from the lowest to the highest fitness
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_WIDTH: usize = 30;
+// redrawing every generation of a small population would slow the run down
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// a line on the terminal which follows the runs of an experiment - the run and its generation,
+/// the best and the mean fitness, the evaluations per second, the estimated time left for all the
+/// runs and a sparkline of the best fitness of the run so far
+/// it is written to stderr and redrawn in place, so the results on stdout stay as they are
+pub struct Progress {
+    runs: u32,
+    generations: u32,
+    budget: Option<u64>,
+    started: Instant,
+    last_drawn: Option<Instant>,
+    // where the experiment was when the display started, e.g. after a resume
+    start: Option<(f64, u64)>,
+    // the evaluations of the runs finished since the display started
+    finished_evaluations: u64,
+    best_fitnesses: Vec<f64>,
+}
+
+impl Progress {
+    /// the runs end after the generations or the evaluation budget, whichever comes first
+    pub fn new(runs: u32, generations: u32, budget: Option<u64>) -> Self {
+        Progress {
+            runs,
+            generations,
+            budget,
+            started: Instant::now(),
+            last_drawn: None,
+            start: None,
+            finished_evaluations: 0,
+            best_fitnesses: Vec::new(),
+        }
+    }
+
+    /// takes the population of a generation before it breeds the next one, like
+    /// Population::write_statistics, the evaluations are those of the run so far
+    pub fn update<G>(
+        &mut self,
+        run: u32,
+        generation: u32,
+        population: &Population<G>,
+        evaluations: u64,
+    ) {
+        let Some(best) = population
+            .specimens
+            .iter()
+            .map(|specimen| specimen.fitness)
+            .min()
+        else {
+            return;
+        };
+        self.best_fitnesses.push(best.value());
+
+        let progress = self.experiment_progress(run, generation, evaluations);
+        let &mut (start_progress, start_evaluations) =
+            self.start.get_or_insert((progress, evaluations));
+
+        let now = Instant::now();
+        if self
+            .last_drawn
+            .is_some_and(|last_drawn| now - last_drawn < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.last_drawn = Some(now);
+
+        let mean = population
+            .specimens
+            .iter()
+            .map(|specimen| specimen.fitness.value())
+            .sum::<f64>()
+            / population.specimens.len() as f64;
+        let elapsed = (now - self.started).as_secs_f64();
+        let done_evaluations =
+            (self.finished_evaluations + evaluations).saturating_sub(start_evaluations);
+        let rate = if elapsed > 0.0 {
+            done_evaluations as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = (progress > start_progress)
+            .then(|| elapsed / (progress - start_progress) * (1.0 - progress))
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+
+        let generations = match self.generations {
+            u32::MAX => String::new(),
+            generations => format!("/{}", generations),
+        };
+        eprint!(
+            "\r\x1b[Krun {}/{}  generation {}{}  best {}  mean {:.1}  {:.0} evals/s  ETA {}  {}",
+            run + 1,
+            self.runs,
+            generation,
+            generations,
+            best,
+            mean,
+            rate,
+            eta.map_or(String::from("--:--"), format_duration),
+            render_sparkline(&self.best_fitnesses, SPARKLINE_WIDTH)
+        );
+        let _ = io::stderr().flush();
+    }
+
+    /// clears the line, so that the results of the run can be printed, and starts a new sparkline
+    pub fn finish_run(&mut self, evaluations: u64) {
+        self.finished_evaluations += evaluations;
+        self.best_fitnesses.clear();
+        self.last_drawn = None;
+
+        eprint!("\r\x1b[K");
+        let _ = io::stderr().flush();
+    }
+
+    // the share of the whole experiment done, a run is as far as its generations or its budget
+    fn experiment_progress(&self, run: u32, generation: u32, evaluations: u64) -> f64 {
+        let by_generations = match self.generations {
+            u32::MAX | 0 => 0.0,
+            generations => generation as f64 / generations as f64,
+        };
+        let by_evaluations = match self.budget {
+            Some(budget) if budget > 0 => evaluations as f64 / budget as f64,
+            _ => 0.0,
+        };
+
+        (run as f64 + by_generations.max(by_evaluations).min(1.0)) / self.runs.max(1) as f64
+    }
+}
+
+/// the values as a line of block characters, the lowest value is the lowest block
+/// with more values than the width, every character stands for the last value of its share
+pub fn render_sparkline(values: &[f64], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let samples: Vec<f64> = if values.len() <= width {
+        values.to_vec()
+    } else {
+        (1..=width)
+            .map(|column| values[column * values.len() / width - 1])
+            .collect()
+    };
+
+    let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    samples
+        .iter()
+        .map(|value| {
+            let level = if max > min {
+                ((value - min) / (max - min) * (SPARKS.len() - 1) as f64).round() as usize
+            } else {
+                0
+            };
+            SPARKS[level.min(SPARKS.len() - 1)]
+        })
+        .collect()
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match seconds / 3600 {
+        0 => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
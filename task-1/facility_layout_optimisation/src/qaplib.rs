@@ -0,0 +1,379 @@
+use crate::distance_model::{DistanceMatrix, DistanceModel};
+use crate::error::ParseError;
+use crate::facility::Facility;
+use crate::facility_configuration::Dimensions;
+use crate::facility_layout::{FacilityFlow, FacilityLayout};
+
+use std::fs;
+use std::io;
+
+/// a QAPLIB instance - n, then the n x n flow matrix, then the n x n distance matrix
+/// the objective is the sum of flow(i, j) * distance(p(i), p(j)) over all facility pairs
+pub struct QapInstance {
+    pub size: usize,
+    pub flows: Vec<u64>,
+    pub distances: DistanceMatrix,
+}
+
+/// a QAPLIB solution - n and the cost, then the (1-based) location of every facility
+pub struct QapSolution {
+    pub cost: u64,
+    /// the 0-based location of every facility
+    pub permutation: Vec<usize>,
+}
+
+impl QapInstance {
+    /// the locations become a single column of cells, so that the crossover always has rows to cut
+    /// the flows carry their amount with a unit cost, the distances come from the matrix
+    pub fn into_problem(self) -> (Dimensions, FacilityLayout) {
+        let size = self.size;
+        let facility_flows = (0..size)
+            .flat_map(|source| (0..size).map(move |dest| (source, dest)))
+            .filter(|(source, dest)| self.flows[source * size + dest] != 0)
+            .map(|(source, dest)| FacilityFlow {
+                source: source as u64,
+                dest: dest as u64,
+                amount: self.flows[source * size + dest] as f64,
+                cost: 1.0,
+            })
+            .collect();
+
+        (
+            Dimensions {
+                width: 1,
+                height: size as u64,
+                machines: (0..size as u64).collect(),
+                fixed_machines: Vec::new(),
+                blocked_cells: Vec::new(),
+            },
+            FacilityLayout {
+                facility_flows,
+                distance_model: DistanceModel::Matrix(self.distances),
+                footprints: None,
+                rel_chart: None,
+            },
+        )
+    }
+
+    /// every cell is a location, the machines are the first facilities and the remaining
+    /// facilities are dummies without any flow, which stand for the empty cells
+    pub fn from_problem(dimensions: &Dimensions, facility_layout: &FacilityLayout) -> Self {
+        let size = (dimensions.width * dimensions.height) as usize;
+        let mut flows = vec![0.0; size * size];
+
+        for flow in &facility_layout.facility_flows {
+            let source = machine_index(dimensions, flow.source);
+            let dest = machine_index(dimensions, flow.dest);
+
+            if let (Some(source), Some(dest)) = (source, dest) {
+                flows[source * size + dest] += flow.amount * flow.cost;
+            }
+        }
+        // QAPLIB only knows integers, so fractional flows are rounded like the distances
+        let flows = flows.iter().map(|flow| flow.round() as u64).collect();
+
+        let distances = DistanceMatrix::from_model(
+            &facility_layout.distance_model,
+            dimensions.width,
+            dimensions.height,
+        );
+
+        QapInstance {
+            size,
+            flows,
+            distances,
+        }
+    }
+}
+
+impl QapSolution {
+    /// the machines are the first facilities, like in QapInstance::from_problem
+    pub fn from_facility(facility: &Facility, cost: u64, dimensions: &Dimensions) -> Self {
+        let size = (dimensions.width * dimensions.height) as usize;
+
+        let mut permutation: Vec<usize> = dimensions
+            .machines
+            .iter()
+            .filter_map(|machine| facility.cell_of(*machine))
+            .collect();
+
+        // the dummy facilities take the empty cells
+        let empty_cells: Vec<usize> = (0..size)
+            .filter(|cell| !permutation.contains(cell))
+            .collect();
+        permutation.extend(empty_cells);
+
+        QapSolution { cost, permutation }
+    }
+
+    pub fn to_facility(&self, dimensions: &Dimensions) -> Facility {
+        let size = (dimensions.width * dimensions.height) as usize;
+        let mut interior = vec![None; size];
+
+        for (machine, location) in dimensions.machines.iter().zip(&self.permutation) {
+            interior[*location] = Some(*machine);
+        }
+
+        Facility::new(interior, dimensions.width)
+    }
+}
+
+pub fn read_instance(path: &str) -> Result<QapInstance, ParseError> {
+    let contents = read_file(path)?;
+    let mut tokens = Tokens::new(path, &contents);
+
+    let size = tokens.next_number("n")? as usize;
+    let flows = tokens.next_numbers(size * size, "flow matrix")?;
+    let distances = tokens.next_numbers(size * size, "distance matrix")?;
+    tokens.expect_end()?;
+
+    Ok(QapInstance {
+        size,
+        flows,
+        distances: DistanceMatrix::new(size, distances.into_iter().map(|d| d as f64).collect())
+            .ok_or_else(|| tokens.malformed("the distance matrix is not square"))?,
+    })
+}
+
+pub fn write_instance(path: &str, instance: &QapInstance) -> io::Result<()> {
+    let size = instance.size;
+    let mut contents = format!("{}\n\n", size);
+
+    contents.push_str(&format_matrix(size, |from, to| {
+        instance.flows[from * size + to]
+    }));
+    contents.push('\n');
+    // QAPLIB only knows integers, so fractional distances are rounded
+    contents.push_str(&format_matrix(size, |from, to| {
+        instance.distances.get(from, to).round() as u64
+    }));
+
+    fs::write(path, contents)
+}
+
+/// the locations of the permutation may be separated by whitespace or commas
+pub fn read_solution(path: &str) -> Result<QapSolution, ParseError> {
+    let contents = read_file(path)?;
+    let mut tokens = Tokens::new(path, &contents);
+
+    let size = tokens.next_number("n")? as usize;
+    let cost = tokens.next_number("cost")?;
+    let permutation: Vec<usize> = tokens
+        .next_numbers(size, "permutation")?
+        .into_iter()
+        .map(|location| location as usize)
+        .collect();
+    tokens.expect_end()?;
+
+    // every location from 1 to n has to be used exactly once
+    let mut used = vec![false; size];
+    for location in &permutation {
+        if *location == 0 || *location > size || used[location - 1] {
+            return Err(tokens.malformed("the solution is not a permutation of 1..n"));
+        }
+        used[location - 1] = true;
+    }
+
+    Ok(QapSolution {
+        cost,
+        permutation: permutation.iter().map(|location| location - 1).collect(),
+    })
+}
+
+pub fn write_solution(path: &str, solution: &QapSolution) -> io::Result<()> {
+    let permutation: Vec<String> = solution
+        .permutation
+        .iter()
+        .map(|location| (location + 1).to_string())
+        .collect();
+
+    fs::write(
+        path,
+        format!(
+            "{} {}\n{}\n",
+            solution.permutation.len(),
+            solution.cost,
+            permutation.join(" ")
+        ),
+    )
+}
+
+fn machine_index(dimensions: &Dimensions, machine: u64) -> Option<usize> {
+    dimensions.machines.iter().position(|m| *m == machine)
+}
+
+fn format_matrix<F>(size: usize, value: F) -> String
+where
+    F: Fn(usize, usize) -> u64,
+{
+    let values: Vec<Vec<String>> = (0..size)
+        .map(|from| (0..size).map(|to| value(from, to).to_string()).collect())
+        .collect();
+    let width = values.iter().flatten().map(String::len).max().unwrap_or(1);
+
+    values
+        .iter()
+        .map(|row| {
+            let row: Vec<String> = row
+                .iter()
+                .map(|value| format!("{:>width$}", value, width = width))
+                .collect();
+            format!("{}\n", row.join(" "))
+        })
+        .collect()
+}
+
+fn read_file(path: &str) -> Result<String, ParseError> {
+    fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+// the QAPLIB files are plain numbers separated by whitespace, the line breaks carry no meaning
+// some published solutions separate the permutation by commas as well
+struct Tokens<'a> {
+    path: &'a str,
+    tokens: std::vec::IntoIter<&'a str>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(path: &'a str, contents: &'a str) -> Self {
+        let tokens: Vec<&str> = contents
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .collect();
+
+        Tokens {
+            path,
+            tokens: tokens.into_iter(),
+        }
+    }
+
+    fn next_number(&mut self, what: &str) -> Result<u64, ParseError> {
+        self.tokens
+            .next()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| self.malformed(&format!("missing or invalid {}", what)))
+    }
+
+    fn next_numbers(&mut self, count: usize, what: &str) -> Result<Vec<u64>, ParseError> {
+        (0..count).map(|_| self.next_number(what)).collect()
+    }
+
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        match self.tokens.next() {
+            Some(_) => Err(self.malformed("unexpected data after the last matrix")),
+            None => Ok(()),
+        }
+    }
+
+    fn malformed(&self, reason: &str) -> ParseError {
+        ParseError::Malformed {
+            path: self.path.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn temporary_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("qaplib_{}_{}", std::process::id(), name))
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn round_trips_instances() {
+        let size = 4;
+        let flows: Vec<u64> = vec![0, 5, 2, 4, 5, 0, 3, 0, 2, 3, 0, 0, 4, 0, 0, 0];
+        let distances: Vec<f64> = vec![
+            0.0, 1.0, 1.0, 2.0, 1.0, 0.0, 2.0, 1.0, 1.0, 2.0, 0.0, 1.0, 2.0, 1.0, 1.0, 0.0,
+        ];
+        let instance = QapInstance {
+            size,
+            flows: flows.clone(),
+            distances: DistanceMatrix::new(size, distances.clone()).unwrap(),
+        };
+
+        let path = temporary_path("instance.dat");
+        write_instance(&path, &instance).unwrap();
+        let read = read_instance(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.size, size);
+        assert_eq!(read.flows, flows);
+
+        // the instance survives the way through the facility problem and back
+        let (dimensions, facility_layout) = read.into_problem();
+        let exported = QapInstance::from_problem(&dimensions, &facility_layout);
+
+        assert_eq!(exported.size, size);
+        assert_eq!(exported.flows, flows);
+        for from in 0..size {
+            for to in 0..size {
+                assert_eq!(
+                    exported.distances.get(from, to),
+                    distances[from * size + to]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_solutions() {
+        let dimensions = Dimensions {
+            width: 3,
+            height: 3,
+            machines: vec![2, 4, 5, 7, 8],
+            fixed_machines: Vec::new(),
+            blocked_cells: Vec::new(),
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let path = temporary_path("solution.sln");
+
+        for cost in 0..20 {
+            let facility = Facility::generate_randomised_facility(&dimensions, &mut rng);
+
+            write_solution(
+                &path,
+                &QapSolution::from_facility(&facility, cost, &dimensions),
+            )
+            .unwrap();
+            let solution = read_solution(&path).unwrap();
+
+            assert_eq!(solution.cost, cost);
+            assert_eq!(solution.to_facility(&dimensions), facility);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_comma_separated_solutions() {
+        let path = temporary_path("commas.sln");
+        fs::write(&path, "4 52\n3,4, 1,\n2\n").unwrap();
+        let solution = read_solution(&path);
+        fs::remove_file(&path).unwrap();
+
+        let solution = solution.unwrap();
+        assert_eq!(solution.cost, 52);
+        assert_eq!(solution.permutation, vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn rejects_solutions_which_are_no_permutation() {
+        let path = temporary_path("invalid.sln");
+        fs::write(&path, "3 10\n1 3 3\n").unwrap();
+        let solution = read_solution(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(solution, Err(ParseError::Malformed { .. })));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::facility::{Facility, LayoutContext};
+use crate::facility_configuration::Dimensions;
+use crate::fitness::Fitness;
+use crate::genome::{Genome, LayoutGenome};
+use serde_json::{json, Value};
+
+use rand::Rng;
+
+/// a layout as one real-valued key for every free cell - the movable machines and the empty slots
+/// are sorted by their keys and put onto the free cells in that order
+/// any vector of keys is a valid layout, so the operators are plain arithmetic on the keys
+/// and need no repair, unlike those of Facility
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomKeys {
+    // the keys of the movable machines come first, in the order of get_movable_machines,
+    // followed by those of the empty slots
+    keys: Vec<f64>,
+}
+
+impl RandomKeys {
+    pub fn new(keys: Vec<f64>) -> Self {
+        RandomKeys { keys }
+    }
+
+    pub fn get_keys(&self) -> &[f64] {
+        &self.keys
+    }
+
+    pub fn decode(&self, dimensions: &Dimensions) -> Facility {
+        let free_cells = dimensions.get_free_cells();
+        let movable_machines = dimensions.get_movable_machines();
+
+        let mut interior: Vec<Option<u64>> =
+            vec![None; (dimensions.width * dimensions.height) as usize];
+        for &(machine, cell) in &dimensions.fixed_machines {
+            interior[cell] = Some(machine);
+        }
+
+        // equal keys keep their order, so that decoding never depends on the sort
+        let mut order: Vec<usize> = (0..self.keys.len()).collect();
+        order.sort_by(|&first, &second| {
+            self.keys[first]
+                .total_cmp(&self.keys[second])
+                .then(first.cmp(&second))
+        });
+
+        for (cell, item) in free_cells.iter().zip(order) {
+            interior[*cell] = movable_machines.get(item).copied();
+        }
+
+        Facility::new(interior, dimensions.width)
+    }
+}
+
+impl Genome for RandomKeys {
+    type Context<'a> = LayoutContext<'a>;
+
+    fn random<R: Rng + ?Sized>(context: &LayoutContext, rng: &mut R) -> Self {
+        let slots = context.dimensions.get_free_cells().len();
+
+        RandomKeys::new((0..slots).map(|_x| rng.gen::<f64>()).collect())
+    }
+
+    // uniform crossover, every key comes from either parent with the same probability
+    fn crossover<R: Rng + ?Sized>(
+        &self,
+        other: &Self,
+        _context: &LayoutContext,
+        rng: &mut R,
+    ) -> (Self, Self) {
+        let (first, second): (Vec<f64>, Vec<f64>) = self
+            .keys
+            .iter()
+            .zip(&other.keys)
+            .map(|(&own, &others)| {
+                if rng.gen_bool(0.5) {
+                    (own, others)
+                } else {
+                    (others, own)
+                }
+            })
+            .unzip();
+
+        (RandomKeys::new(first), RandomKeys::new(second))
+    }
+
+    // a mutated key is drawn anew
+    fn mutate<R: Rng + ?Sized>(
+        &mut self,
+        mutation_factor: f64,
+        _context: &LayoutContext,
+        rng: &mut R,
+    ) {
+        for key in &mut self.keys {
+            if rng.gen_bool(mutation_factor) {
+                *key = rng.gen::<f64>();
+            }
+        }
+    }
+
+    fn evaluate(&self, context: &LayoutContext) -> Fitness {
+        self.decode(context.dimensions)
+            .calculate_fitness(context.facility_layout)
+    }
+}
+
+impl LayoutGenome for RandomKeys {
+    fn to_facility(&self, dimensions: &Dimensions) -> Facility {
+        self.decode(dimensions)
+    }
+
+    fn to_value(&self) -> Value {
+        json!({ "keys": self.keys })
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        value["keys"]
+            .as_array()?
+            .iter()
+            .map(Value::as_f64)
+            .collect::<Option<Vec<f64>>>()
+            .map(RandomKeys::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceModel;
+    use crate::facility_layout::FacilityLayout;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // a 3x2 grid with a pillar on cell 1 and machine 3 fixed on cell 5
+    fn dimensions() -> Dimensions {
+        Dimensions {
+            width: 3,
+            height: 2,
+            machines: vec![0, 1, 2, 3],
+            fixed_machines: vec![(3, 5)],
+            blocked_cells: vec![1],
+        }
+    }
+
+    #[test]
+    fn decodes_the_keys_in_ascending_order() {
+        // machines 0, 1, 2 and the empty slot, put onto the free cells 0, 2, 3 and 4
+        let facility = RandomKeys::new(vec![0.7, 0.1, 0.4, 0.2]).decode(&dimensions());
+
+        assert_eq!(
+            facility,
+            Facility::new(vec![Some(1), None, None, Some(2), Some(0), Some(3)], 3)
+        );
+    }
+
+    #[test]
+    fn decodes_equal_keys_in_their_order() {
+        let facility = RandomKeys::new(vec![0.5; 4]).decode(&dimensions());
+
+        assert_eq!(
+            facility,
+            Facility::new(vec![Some(0), None, Some(1), Some(2), None, Some(3)], 3)
+        );
+    }
+
+    #[test]
+    fn decodes_any_keys_to_a_valid_layout() {
+        let dimensions = dimensions();
+        let facility_layout = FacilityLayout {
+            facility_flows: Vec::new(),
+            distance_model: DistanceModel::Manhattan,
+            footprints: None,
+            rel_chart: None,
+        };
+        let context = LayoutContext::new(&dimensions, &facility_layout);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let facility = RandomKeys::random(&context, &mut rng).decode(&dimensions);
+
+            // every machine is placed exactly once
+            let mut machines: Vec<u64> = (0..dimensions.height)
+                .flat_map(|row| (0..dimensions.width).map(move |column| (row, column)))
+                .filter_map(|(row, column)| facility.get_machine(row, column))
+                .collect();
+            machines.sort_unstable();
+            assert_eq!(machines, dimensions.machines);
+            assert_eq!(facility.cell_of(3), Some(5));
+            assert_eq!(facility.get_machine(0, 1), None);
+        }
+    }
+}
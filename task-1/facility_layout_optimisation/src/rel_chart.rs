@@ -0,0 +1,172 @@
+use crate::error::ParseError;
+use crate::facility::Facility;
+use crate::flow_parser::parse_field;
+use crate::footprint::{placed_facility, Footprints};
+use serde_json::Value;
+
+use std::collections::HashSet;
+use std::fs;
+
+/// Muther's closeness ratings, from absolutely necessary to undesirable
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rating {
+    A,
+    E,
+    I,
+    O,
+    U,
+    X,
+}
+
+impl Rating {
+    pub fn from_letter(letter: &str) -> Option<Self> {
+        match letter {
+            "A" => Some(Rating::A),
+            "E" => Some(Rating::E),
+            "I" => Some(Rating::I),
+            "O" => Some(Rating::O),
+            "U" => Some(Rating::U),
+            "X" => Some(Rating::X),
+            _ => None,
+        }
+    }
+
+    // the penalty for the pair not being adjacent, or for being adjacent in the case of X
+    // every rating weighs three times as much as the next weaker one
+    fn penalty(&self) -> f64 {
+        match self {
+            Rating::A => 81.0,
+            Rating::E => 27.0,
+            Rating::I => 9.0,
+            Rating::O => 3.0,
+            Rating::U => 0.0,
+            Rating::X => 243.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ClosenessRating {
+    pub first: u64,
+    pub second: u64,
+    pub rating: Rating,
+}
+
+/// the closeness ratings together with the weights they are combined with the flow cost by
+#[derive(Clone, Debug)]
+pub struct RelChart {
+    pub ratings: Vec<ClosenessRating>,
+    pub flow_weight: f64,
+    pub closeness_weight: f64,
+}
+
+impl RelChart {
+    /// never negative, so a layout with every A to X pair satisfied has no penalty at all
+    /// the machines with footprints are adjacent if any of their cells are, a layout whose
+    /// footprints cannot be placed violates every rating
+    pub fn penalty(&self, facility: &Facility, footprints: Option<&Footprints>) -> f64 {
+        let placed;
+        let facility = match footprints.map(|footprints| footprints.place(facility)) {
+            Some(Some(placement)) => {
+                placed = placed_facility(&placement, facility.get_width(), facility.get_height());
+                &placed
+            }
+            Some(None) => {
+                return self
+                    .ratings
+                    .iter()
+                    .map(|closeness| closeness.rating.penalty())
+                    .sum()
+            }
+            None => facility,
+        };
+
+        self.ratings
+            .iter()
+            .filter(|closeness| {
+                let adjacent = facility.are_adjacent(closeness.first, closeness.second);
+                (closeness.rating == Rating::X) == adjacent
+            })
+            .map(|closeness| closeness.rating.penalty())
+            .sum()
+    }
+}
+
+/// [{"source": 0, "dest": 3, "rating": "A"}], the pairs are unordered and the missing ones are U
+pub fn read_closeness_ratings(
+    path: &str,
+    machines: &[u64],
+) -> Result<Vec<ClosenessRating>, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    let entries = match serde_json::from_str(&contents) {
+        Ok(Value::Array(entries)) => entries,
+        Ok(_) => {
+            return Err(ParseError::NotAnArray {
+                path: path.to_string(),
+            })
+        }
+        Err(source) => {
+            return Err(ParseError::Json {
+                path: path.to_string(),
+                source,
+            })
+        }
+    };
+
+    let mut pairs: HashSet<(u64, u64)> = HashSet::new();
+    let mut ratings: Vec<ClosenessRating> = Vec::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let source = parse_field(entry, path, index, "source")?;
+        let dest = parse_field(entry, path, index, "dest")?;
+
+        for machine in [source, dest] {
+            if !machines.contains(&machine) {
+                return Err(ParseError::UnknownMachine {
+                    path: path.to_string(),
+                    index,
+                    machine,
+                });
+            }
+        }
+
+        let rating = match entry.get("rating") {
+            Some(Value::String(letter)) => {
+                Rating::from_letter(letter).ok_or_else(|| ParseError::Malformed {
+                    path: path.to_string(),
+                    reason: format!(
+                        "entry {} has the rating '{}', expected one of A, E, I, O, U or X",
+                        index, letter
+                    ),
+                })?
+            }
+            _ => {
+                return Err(ParseError::MissingField {
+                    path: path.to_string(),
+                    index,
+                    field: "rating",
+                })
+            }
+        };
+
+        if !pairs.insert((source.min(dest), source.max(dest))) {
+            return Err(ParseError::DuplicatePair {
+                path: path.to_string(),
+                index,
+                source,
+                dest,
+            });
+        }
+
+        ratings.push(ClosenessRating {
+            first: source,
+            second: dest,
+            rating,
+        });
+    }
+
+    Ok(ratings)
+}
@@ -0,0 +1,102 @@
+use crate::error::ParseError;
+use std::fs;
+
+/// a single line of a run log - the statistics of one generation
+#[derive(Clone, Debug)]
+pub struct GenerationRecord {
+    pub best: f64,
+    pub worst: f64,
+    pub average: f64,
+    pub deviation: f64,
+}
+
+/// the contents of one results file, as written by Simulation::step
+/// every run is a block of per-generation lines, blocks are separated by empty lines
+#[derive(Debug)]
+pub struct RunLog {
+    pub name: String,
+    pub runs: Vec<Vec<GenerationRecord>>,
+}
+
+impl RunLog {
+    /// the best fitness of the last generation of every run
+    pub fn final_best_fitnesses(&self) -> Vec<f64> {
+        self.runs
+            .iter()
+            .filter_map(|run| run.last())
+            .map(|record| record.best)
+            .collect()
+    }
+}
+
+/// reads a results file, the columns are matched by the header if there is one
+pub fn read_run_log(path: &str) -> Result<RunLog, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParseError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+
+    let mut lines = contents.lines().enumerate().peekable();
+
+    // the column order is taken from the header, so that extra columns do not break parsing
+    let mut columns = vec!["best", "worst", "average", "deviation"];
+    if let Some((_, header)) = lines.peek() {
+        if header.chars().any(|c| c.is_ascii_alphabetic()) {
+            columns = header.split(',').map(str::trim).collect();
+            lines.next();
+        }
+    }
+
+    let column_index = |name: &str| {
+        columns
+            .iter()
+            .position(|column| *column == name)
+            .ok_or_else(|| ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("missing the '{}' column", name),
+            })
+    };
+    let best = column_index("best")?;
+    let worst = column_index("worst")?;
+    let average = column_index("average")?;
+    let deviation = column_index("deviation")?;
+
+    let mut runs: Vec<Vec<GenerationRecord>> = Vec::new();
+    let mut current_run: Vec<GenerationRecord> = Vec::new();
+
+    for (line_index, line) in lines {
+        if line.trim().is_empty() {
+            if !current_run.is_empty() {
+                runs.push(std::mem::take(&mut current_run));
+            }
+            continue;
+        }
+
+        let values = line
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .ok()
+            .filter(|values| values.len() == columns.len())
+            .ok_or_else(|| ParseError::Malformed {
+                path: path.to_string(),
+                reason: format!("line {}: malformed statistics line", line_index + 1),
+            })?;
+
+        current_run.push(GenerationRecord {
+            best: values[best],
+            worst: values[worst],
+            average: values[average],
+            deviation: values[deviation],
+        });
+    }
+
+    if !current_run.is_empty() {
+        runs.push(current_run);
+    }
+
+    Ok(RunLog {
+        name: path.to_string(),
+        runs,
+    })
+}
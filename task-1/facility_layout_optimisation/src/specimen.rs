@@ -1,6 +1,9 @@
-use crate::{generate_randomised_facilities, Dimensions, Facility, FacilityLayout};
+use crate::error::PopulationError;
+use crate::evaluator::Evaluator;
+use crate::facility::Facility;
+use crate::fitness::Fitness;
+use crate::genome::Genome;
 use std::cmp::Ordering::Equal;
-use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write;
 
@@ -8,59 +11,76 @@ use rand::seq::SliceRandom;
 use rand::Rng;
 
 #[derive(Clone, Debug)]
-pub struct Specimen {
-    pub facility: Facility,
-    pub fitness: u64,
+pub struct Specimen<G = Facility> {
+    pub genome: G,
+    pub fitness: Fitness,
 }
 
-impl Specimen {
-    pub fn new(facility: Facility, fitness: u64) -> Self {
-        Specimen { facility, fitness }
+impl<G> Specimen<G> {
+    pub fn new(genome: G, fitness: Fitness) -> Self {
+        Specimen { genome, fitness }
     }
 }
 
-pub struct Population {
-    pub specimens: Vec<Specimen>,
+pub struct Population<G = Facility> {
+    pub specimens: Vec<Specimen<G>>,
 }
 
-impl Population {
-    pub fn fit_facilities(facility_population: Vec<Facility>, layout: &FacilityLayout) -> Self {
+impl<G: Genome> Population<G> {
+    pub fn fit(genomes: Vec<G>, evaluator: &Evaluator<G>) -> Self {
         Self {
-            specimens: facility_population
+            specimens: genomes
                 .into_iter()
-                .map(|facility| {
-                    let fitness = facility.calculate_fitness(layout);
-                    Specimen::new(facility, fitness)
+                .map(|genome| {
+                    let fitness = evaluator.evaluate(&genome);
+                    Specimen::new(genome, fitness)
                 })
                 .collect(),
         }
     }
 
-    pub fn select_by_tournament(&self, tournament_size: u64) -> Result<&Specimen, &'static str> {
+    pub fn random<R: Rng + ?Sized>(size: u32, evaluator: &Evaluator<G>, rng: &mut R) -> Self {
+        let genomes: Vec<G> = (0..size)
+            .map(|_x| G::random(evaluator.context, rng))
+            .collect();
+
+        Population::fit(genomes, evaluator)
+    }
+
+    pub fn select_by_tournament<R: Rng + ?Sized>(
+        &self,
+        tournament_size: u64,
+        rng: &mut R,
+    ) -> Result<&Specimen<G>, PopulationError> {
         if tournament_size as usize > self.specimens.len() {
-            return Err("The tournament size must be less than or equal the total specimen count.");
+            return Err(PopulationError::TournamentTooLarge {
+                tournament_size,
+                population_size: self.specimens.len(),
+            });
         }
 
-        let rng = &mut rand::thread_rng();
-
         self.specimens
             .choose_multiple(rng, tournament_size as usize)
             .min_by(|first, second| first.fitness.cmp(&second.fitness))
-            .ok_or("There are no specimens to choose from.")
+            .ok_or(PopulationError::EmptyPopulation)
     }
 
-    pub fn select_by_roulette(&self) -> Result<&Specimen, &'static str> {
-        let square_fitness_sum = self.specimens.iter().fold(0, |sum_acc, specimen| {
-            sum_acc + specimen.fitness * specimen.fitness
-        });
+    pub fn select_by_roulette<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<&Specimen<G>, PopulationError> {
+        let square_fitness_sum: f64 = self
+            .specimens
+            .iter()
+            .map(|specimen| specimen.fitness.value().powi(2))
+            .sum();
 
-        let mut roulette_specimens: Vec<RouletteSpecimen> = self
+        let mut roulette_specimens: Vec<RouletteSpecimen<G>> = self
             .specimens
             .iter()
             .map(|specimen| RouletteSpecimen {
                 specimen,
-                likelihood: (specimen.fitness * specimen.fitness) as f32
-                    / (square_fitness_sum as f32),
+                likelihood: (specimen.fitness.value().powi(2) / square_fitness_sum) as f32,
                 likelihood_bound: None,
             })
             .collect();
@@ -95,193 +115,87 @@ impl Population {
 
         // TODO functionally?
         let mut current_likelihood_bound = 0.0;
-        for mut roulette_specimen in &mut roulette_specimens {
+        for roulette_specimen in &mut roulette_specimens {
             current_likelihood_bound += roulette_specimen.likelihood;
             roulette_specimen.likelihood_bound = Some(current_likelihood_bound);
         }
 
         // get the roulette guess
-        let mut rng = rand::thread_rng();
-
         let guess = rng.gen_range(0.0..=current_likelihood_bound);
 
         roulette_specimens
             .iter()
             .find(|specimen| guess <= specimen.likelihood_bound.unwrap_or(0.0))
             .map(|roulette_specimen| roulette_specimen.specimen)
-            .ok_or("No specimen has likelihood_bound as high as the guess.")
+            .ok_or(PopulationError::RouletteMiss)
     }
 
-    // TODO fix this...
-    #[allow(clippy::too_many_arguments)]
-    pub fn simulate_tournament<F>(
-        population_size: u32,
-        dimensions: &Dimensions,
-        facility_layout: &FacilityLayout,
-        selection_function: F,
-        crossover_factor: f64,
-        mutation_factor: f64,
-        runs: u32,
+    /// appends the best, worst and average fitness, its deviation and the evaluations of the run
+    /// so far to the results file
+    pub fn write_statistics(
+        &self,
         file_name: &str,
-    ) -> Result<u64, &'static str>
-    where
-        F: Fn(&Population) -> Result<&Specimen, &'static str>,
-    {
-        // TODO fix this...
-        #[allow(clippy::too_many_arguments)]
-        fn simulate<F>(
-            previous_population: Population,
-            facility_layout: &FacilityLayout,
-            crossover_factor: f64,
-            mutation_factor: f64,
-            selection_function: F,
-            // TODO this should probably be a field in Population?
-            max_machine: u64,
-            runs: u32,
-            runs_elapsed: u32,
-            file_name: &str,
-        ) -> Result<u64, &'static str>
-        where
-            F: Fn(&Population) -> Result<&Specimen, &'static str>,
-        {
-            // step 0. - write the simulation statistics
-            let stats = Population::calculate_statistics(&previous_population.specimens)
-                .unwrap_or((0, 0, 0.0, 0.0));
-
-            let mut file = OpenOptions::new().append(true).open(file_name).unwrap();
-            writeln!(file, "{},{},{},{}", stats.0, stats.1, stats.2, stats.3)
-                .expect("Unable to write file");
-
-            // check the exit condition
-            if runs_elapsed == runs {
-                return Ok(previous_population
-                    .specimens
-                    .iter()
-                    .min_by(|first, second| first.fitness.cmp(&second.fitness))
-                    .ok_or("TODO")?
-                    .fitness);
-            }
+        evaluations: u64,
+    ) -> Result<(), PopulationError> {
+        let stats = Population::calculate_statistics(&self.specimens).unwrap_or_default();
 
-            // step 1. - selection
-            let selection: Vec<&Specimen> = (0..previous_population.specimens.len())
-                .map(|_x| selection_function(&previous_population))
-                .into_iter()
-                .collect::<Result<Vec<&Specimen>, &str>>()?;
-
-            // step 2. - crossover
-            // each specimen is chosen for crossover with a given probability
-            // they are then connected into pairs
-            // if there is an uneven amount of crossover specimens, the last one is just copied
-            let mut new_population: Vec<Specimen> = Vec::new();
-            let mut crossover_specimens: Vec<&Specimen> = Vec::new();
-            let mut rng = rand::thread_rng();
-
-            for specimen in selection {
-                if rng.gen_bool(crossover_factor) {
-                    crossover_specimens.push(specimen);
-                } else {
-                    new_population.push(specimen.clone());
-                }
-            }
-
-            crossover_specimens.shuffle(&mut rng);
-
-            if crossover_specimens.len() % 2 != 0 {
-                new_population.push(crossover_specimens.pop().ok_or("TODO")?.clone());
-            }
-
-            // the actual crossover takes place here
-            new_population.append(
-                &mut crossover_specimens
-                    .chunks_exact(2)
-                    .flat_map(|crossover_chunk_iter| {
-                        // TODO remove unwrap?
-                        let [first, second]: [&Specimen; 2] =
-                            crossover_chunk_iter.try_into().unwrap();
-                        let result = first.facility.crossover(&second.facility);
-
-                        Population::fit_facilities(vec![result.0, result.1], facility_layout)
-                            .specimens
-                    })
-                    .collect(),
-            );
-
-            // step 3. - mutation
-            // each specimen is mutated with a given probability
-            for specimen in &mut new_population {
-                specimen.facility.mutate(mutation_factor, max_machine);
-            }
-
-            // TODO shouldn't this be the first step?
-            // step 3.5. - refit the population after mutation
-            for specimen in &mut new_population {
-                specimen.fitness = specimen.facility.calculate_fitness(facility_layout);
-            }
-
-            // step 4. - call the next iteration
-            simulate(
-                Population {
-                    specimens: new_population,
-                },
-                facility_layout,
-                crossover_factor,
-                mutation_factor,
-                selection_function,
-                max_machine,
-                runs,
-                runs_elapsed + 1,
-                file_name,
-            )
-        }
+        self.write_statistics_with_best(file_name, stats.0, evaluations)
+    }
 
-        let starting_population = Population::fit_facilities(
-            generate_randomised_facilities(dimensions, population_size),
-            facility_layout,
-        );
+    /// like write_statistics, but with the best fitness found so far rather than the population's,
+    /// for solvers whose population does not keep its best specimen, e.g. the ants of an iteration
+    pub fn write_statistics_with_best(
+        &self,
+        file_name: &str,
+        best: Fitness,
+        evaluations: u64,
+    ) -> Result<(), PopulationError> {
+        let stats = Population::calculate_statistics(&self.specimens).unwrap_or_default();
+
+        let mut file = OpenOptions::new().append(true).open(file_name)?;
+        writeln!(
+            file,
+            "{},{},{},{},{}",
+            best, stats.1, stats.2, stats.3, evaluations
+        )?;
+
+        Ok(())
+    }
 
-        let max_machine = *starting_population
-            .specimens
-            .first()
-            .and_then(|specimen| specimen.facility.find_max_machine())
-            .expect("TODO");
-
-        simulate(
-            starting_population,
-            facility_layout,
-            crossover_factor,
-            mutation_factor,
-            selection_function,
-            max_machine,
-            runs,
-            0,
-            file_name,
-        )
+    /// the specimen with the lowest fitness
+    pub fn get_best(&self) -> Result<&Specimen<G>, PopulationError> {
+        self.specimens
+            .iter()
+            .min_by(|first, second| first.fitness.cmp(&second.fitness))
+            .ok_or(PopulationError::EmptyPopulation)
     }
 
-    fn calculate_statistics(specimens: &[Specimen]) -> Result<(u64, u64, f32, f32), &'static str> {
+    fn calculate_statistics(
+        specimens: &[Specimen<G>],
+    ) -> Result<(Fitness, Fitness, f64, f64), PopulationError> {
         let best_fitness = specimens
             .iter()
             .min_by(|first, second| first.fitness.cmp(&second.fitness))
-            .ok_or("TODO")?
+            .ok_or(PopulationError::EmptyPopulation)?
             .fitness;
         let worst_fitness = specimens
             .iter()
             .max_by(|first, second| first.fitness.cmp(&second.fitness))
-            .ok_or("TODO")?
+            .ok_or(PopulationError::EmptyPopulation)?
             .fitness;
         let average_fitness = specimens
             .iter()
-            .map(|specimen| specimen.fitness)
-            .sum::<u64>() as f32
-            / specimens.len() as f32;
+            .map(|specimen| specimen.fitness.value())
+            .sum::<f64>()
+            / specimens.len() as f64;
         let standard_deviation = (specimens
             .iter()
             .map(|specimen| {
-                let diff = average_fitness - specimen.fitness as f32;
+                let diff = average_fitness - specimen.fitness.value();
                 diff * diff
             })
-            .sum::<f32>()
-            / specimens.len() as f32)
+            .sum::<f64>()
+            / specimens.len() as f64)
             .sqrt();
 
         Ok((
@@ -293,8 +207,236 @@ impl Population {
     }
 }
 
-struct RouletteSpecimen<'a> {
-    pub specimen: &'a Specimen,
+/// how the parents of the next generation are chosen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection {
+    Tournament(u64),
+    Roulette,
+    Random,
+}
+
+impl Selection {
+    pub fn select<'a, G: Genome, R: Rng + ?Sized>(
+        &self,
+        population: &'a Population<G>,
+        rng: &mut R,
+    ) -> Result<&'a Specimen<G>, PopulationError> {
+        match self {
+            Selection::Tournament(tournament_size) => {
+                population.select_by_tournament(*tournament_size, rng)
+            }
+            Selection::Roulette => population.select_by_roulette(rng),
+            Selection::Random => population
+                .specimens
+                .choose(rng)
+                .ok_or(PopulationError::EmptyPopulation),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulationParameters {
+    pub population_size: u32,
+    pub generations: u32,
+    pub selection: Selection,
+    pub crossover_factor: f64,
+    pub mutation_factor: f64,
+}
+
+/// a single run of the genetic algorithm, one generation at a time, so that it can be interrupted
+/// the random number generator is passed in, since all the runs of an experiment share it
+pub struct Simulation<G = Facility> {
+    pub parameters: SimulationParameters,
+    pub population: Population<G>,
+    pub generation: u32,
+}
+
+impl<G: Genome> Simulation<G> {
+    pub fn new<R: Rng + ?Sized>(
+        parameters: SimulationParameters,
+        evaluator: &Evaluator<G>,
+        rng: &mut R,
+    ) -> Self {
+        let population = Population::random(parameters.population_size, evaluator, rng);
+
+        Simulation::resume(parameters, population, 0)
+    }
+
+    /// continues with the given population, e.g. one read from a checkpoint
+    pub fn resume(
+        parameters: SimulationParameters,
+        population: Population<G>,
+        generation: u32,
+    ) -> Self {
+        Simulation {
+            parameters,
+            population,
+            generation,
+        }
+    }
+
+    /// writes the statistics of the current generation and breeds the next one,
+    /// once the last generation is reached or the evaluation budget is spent,
+    /// its best specimen is returned instead
+    pub fn step<R: Rng + ?Sized>(
+        &mut self,
+        evaluator: &Evaluator<G>,
+        rng: &mut R,
+        file_name: &str,
+    ) -> Result<Option<Specimen<G>>, PopulationError> {
+        let parameters = &self.parameters;
+        let context = evaluator.context;
+
+        // step 0. - write the simulation statistics
+        self.population
+            .write_statistics(file_name, evaluator.get_evaluations())?;
+
+        // check the exit condition
+        if self.generation == parameters.generations || evaluator.is_exhausted() {
+            return self.population.get_best().cloned().map(Some);
+        }
+
+        // step 1. - selection
+        let selection: Vec<&Specimen<G>> = (0..self.population.specimens.len())
+            .map(|_x| parameters.selection.select(&self.population, rng))
+            .collect::<Result<Vec<&Specimen<G>>, PopulationError>>()?;
+
+        // step 2. - crossover
+        // each specimen is chosen for crossover with a given probability
+        // they are then connected into pairs
+        // if there is an uneven amount of crossover specimens, the last one is just copied
+        let mut new_population: Vec<Specimen<G>> = Vec::new();
+        let mut crossover_specimens: Vec<&Specimen<G>> = Vec::new();
+
+        for specimen in selection {
+            if rng.gen_bool(parameters.crossover_factor) {
+                crossover_specimens.push(specimen);
+            } else {
+                new_population.push(specimen.clone());
+            }
+        }
+
+        crossover_specimens.shuffle(rng);
+
+        if !crossover_specimens.len().is_multiple_of(2) {
+            new_population.push(
+                crossover_specimens
+                    .pop()
+                    .ok_or(PopulationError::EmptyPopulation)?
+                    .clone(),
+            );
+        }
+
+        // the actual crossover takes place here
+        // the children are only evaluated after the mutation
+        let copies = new_population.len();
+        for crossover_chunk in crossover_specimens.chunks_exact(2) {
+            let result =
+                crossover_chunk[0]
+                    .genome
+                    .crossover(&crossover_chunk[1].genome, context, rng);
+
+            new_population.push(Specimen::new(result.0, Fitness::default()));
+            new_population.push(Specimen::new(result.1, Fitness::default()));
+        }
+
+        // step 3. - mutation
+        // each specimen is mutated with a given probability
+        // step 3.5. - refit the children and the copies the mutation changed
+        // once the budget is spent, those left without a fitness are dropped from the generation
+        let mut specimens: Vec<Specimen<G>> = Vec::with_capacity(new_population.len());
+        for (index, mut specimen) in new_population.into_iter().enumerate() {
+            let copy = (index < copies).then(|| specimen.genome.clone());
+
+            specimen
+                .genome
+                .mutate(parameters.mutation_factor, context, rng);
+
+            if copy.is_none_or(|copy| copy != specimen.genome) {
+                if evaluator.is_exhausted() {
+                    continue;
+                }
+                specimen.fitness = evaluator.evaluate(&specimen.genome);
+            }
+            specimens.push(specimen);
+        }
+
+        // step 4. - continue with the next generation
+        self.population = Population { specimens };
+        self.generation += 1;
+
+        Ok(None)
+    }
+}
+
+struct RouletteSpecimen<'a, G> {
+    pub specimen: &'a Specimen<G>,
     pub likelihood: f32,
     pub likelihood_bound: Option<f32>, // TODO this should maybe be calculated at creation
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_model::DistanceModel;
+    use crate::facility::LayoutContext;
+    use crate::facility_configuration::Dimensions;
+    use crate::facility_layout::{FacilityFlow, FacilityLayout};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::fs;
+
+    #[test]
+    fn stops_evaluating_at_the_budget() {
+        let dimensions = Dimensions {
+            width: 4,
+            height: 4,
+            machines: (0..10).collect(),
+            fixed_machines: Vec::new(),
+            blocked_cells: Vec::new(),
+        };
+        let facility_layout = FacilityLayout {
+            facility_flows: (1..10)
+                .map(|dest| FacilityFlow {
+                    source: dest - 1,
+                    dest,
+                    amount: dest as f64,
+                    cost: 1.0,
+                })
+                .collect(),
+            distance_model: DistanceModel::Manhattan,
+            footprints: None,
+            rel_chart: None,
+        };
+        let context = LayoutContext::new(&dimensions, &facility_layout);
+        let file_name = std::env::temp_dir().join(format!("specimen_{}.txt", std::process::id()));
+        let file_name = file_name.to_str().unwrap();
+        fs::write(file_name, "").unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        // the budget ends in the middle of the third generation
+        let evaluator: Evaluator<Facility> = Evaluator::new(&context, Some(55));
+        let mut simulation = Simulation::new(
+            SimulationParameters {
+                population_size: 20,
+                generations: 100,
+                selection: Selection::Tournament(3),
+                crossover_factor: 0.75,
+                mutation_factor: 0.25,
+            },
+            &evaluator,
+            &mut rng,
+        );
+        while simulation
+            .step(&evaluator, &mut rng, file_name)
+            .unwrap()
+            .is_none()
+        {}
+        fs::remove_file(file_name).unwrap();
+
+        assert_eq!(evaluator.get_evaluations(), 55);
+        assert!(simulation.population.specimens.iter().all(|specimen| {
+            specimen.fitness == specimen.genome.calculate_fitness(&facility_layout)
+        }));
+    }
+}
@@ -0,0 +1,568 @@
+use crate::error::ConfigError;
+use crate::run_log::RunLog;
+use std::cmp::Ordering::Equal;
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const BOOTSTRAP_RESAMPLES: usize = 10_000;
+const BOOTSTRAP_SEED: u64 = 0;
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// descriptive statistics of the final best fitnesses of one configuration
+#[derive(Debug)]
+pub struct Summary {
+    pub name: String,
+    pub runs: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub deviation: f64,
+    pub mean_confidence_interval: (f64, f64),
+}
+
+impl Summary {
+    fn new(name: &str, values: &[f64], rng: &mut StdRng) -> Self {
+        Summary {
+            name: name.to_string(),
+            runs: values.len(),
+            mean: mean(values),
+            median: median(values),
+            deviation: sample_deviation(values),
+            mean_confidence_interval: bootstrap_confidence_interval(rng, |rng| {
+                mean(&resample(values, rng))
+            }),
+        }
+    }
+}
+
+/// result of a rank-based hypothesis test, using the normal approximation
+#[derive(Debug)]
+pub struct RankTest {
+    pub statistic: f64,
+    pub z: f64,
+    pub p_value: f64,
+}
+
+#[derive(Debug)]
+pub struct Comparison {
+    pub first: String,
+    pub second: String,
+    pub mann_whitney: Option<RankTest>,
+    /// Holm-Bonferroni adjusted Mann-Whitney p-value, accounting for all the pairwise comparisons
+    pub adjusted_p_value: Option<f64>,
+    /// only computed for paired runs, where the i-th runs of both configurations belong together,
+    /// e.g. because they share their seed or instance, and both have the same number of runs
+    pub wilcoxon: Option<RankTest>,
+    // probability that a run of the first configuration finds a better (lower) fitness
+    pub vargha_delaney_a12: f64,
+    pub cliffs_delta: f64,
+    pub cohens_d: f64,
+    pub mean_difference: f64,
+    pub mean_difference_confidence_interval: (f64, f64),
+}
+
+pub struct ComparisonReport {
+    pub summaries: Vec<Summary>,
+    pub comparisons: Vec<Comparison>,
+    pub paired: bool,
+}
+
+impl ComparisonReport {
+    /// independent runs are compared with the Mann-Whitney U test only, the Wilcoxon signed-rank
+    /// test pairs the runs by their index and is only meaningful if they are paired
+    pub fn new(run_logs: &[RunLog], paired: bool) -> Result<Self, ConfigError> {
+        if run_logs.len() < 2 {
+            return Err(ConfigError::TooFewConfigurations {
+                configurations: run_logs.len(),
+            });
+        }
+
+        let samples: Vec<(&str, Vec<f64>)> = run_logs
+            .iter()
+            .map(|run_log| (run_log.name.as_str(), run_log.final_best_fitnesses()))
+            .collect();
+
+        if let Some((name, values)) = samples.iter().find(|(_, values)| values.len() < 2) {
+            return Err(ConfigError::TooFewRuns {
+                configuration: name.to_string(),
+                runs: values.len(),
+            });
+        }
+
+        let mut rng = StdRng::seed_from_u64(BOOTSTRAP_SEED);
+
+        let summaries = samples
+            .iter()
+            .map(|(name, values)| Summary::new(name, values, &mut rng))
+            .collect();
+
+        let mut comparisons: Vec<Comparison> = Vec::new();
+        for (i, (first_name, first)) in samples.iter().enumerate() {
+            for (second_name, second) in samples.iter().skip(i + 1) {
+                comparisons.push(Comparison {
+                    first: first_name.to_string(),
+                    second: second_name.to_string(),
+                    mann_whitney: mann_whitney_u(first, second),
+                    adjusted_p_value: None,
+                    wilcoxon: paired
+                        .then(|| wilcoxon_signed_rank(first, second))
+                        .flatten(),
+                    vargha_delaney_a12: vargha_delaney_a12(first, second),
+                    cliffs_delta: 2.0 * vargha_delaney_a12(first, second) - 1.0,
+                    cohens_d: cohens_d(first, second),
+                    mean_difference: mean(first) - mean(second),
+                    mean_difference_confidence_interval: bootstrap_confidence_interval(
+                        &mut rng,
+                        |rng| mean(&resample(first, rng)) - mean(&resample(second, rng)),
+                    ),
+                });
+            }
+        }
+
+        adjust_with_holm(&mut comparisons);
+
+        Ok(ComparisonReport {
+            summaries,
+            comparisons,
+            paired,
+        })
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "final best fitness per run (lower is better)")?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<32} {:>5} {:>12} {:>12} {:>12}   95% CI of mean",
+            "configuration", "runs", "mean", "median", "std dev"
+        )?;
+        for summary in &self.summaries {
+            writeln!(
+                f,
+                "{:<32} {:>5} {:>12.2} {:>12.2} {:>12.2}   [{:.2}, {:.2}]",
+                summary.name,
+                summary.runs,
+                summary.mean,
+                summary.median,
+                summary.deviation,
+                summary.mean_confidence_interval.0,
+                summary.mean_confidence_interval.1
+            )?;
+        }
+
+        for comparison in &self.comparisons {
+            writeln!(f)?;
+            writeln!(f, "{} vs {}", comparison.first, comparison.second)?;
+
+            match (&comparison.mann_whitney, comparison.adjusted_p_value) {
+                (Some(test), Some(adjusted)) => writeln!(
+                    f,
+                    "  Mann-Whitney U = {:.1}, z = {:.3}, p = {:.4} (Holm-adjusted p = {:.4})",
+                    test.statistic, test.z, test.p_value, adjusted
+                )?,
+                _ => writeln!(f, "  Mann-Whitney U: not applicable (no variance)")?,
+            }
+
+            match &comparison.wilcoxon {
+                Some(test) => writeln!(
+                    f,
+                    "  Wilcoxon signed-rank W+ = {:.1}, z = {:.3}, p = {:.4}",
+                    test.statistic, test.z, test.p_value
+                )?,
+                None if self.paired => writeln!(
+                    f,
+                    "  Wilcoxon signed-rank: not applicable (unequal run counts or no differences)"
+                )?,
+                None => {}
+            }
+
+            writeln!(
+                f,
+                "  A12 = {:.3}, Cliff's delta = {:.3}, Cohen's d = {:.3}",
+                comparison.vargha_delaney_a12, comparison.cliffs_delta, comparison.cohens_d
+            )?;
+            writeln!(
+                f,
+                "  mean difference = {:.2}, 95% bootstrap CI [{:.2}, {:.2}]",
+                comparison.mean_difference,
+                comparison.mean_difference_confidence_interval.0,
+                comparison.mean_difference_confidence_interval.1
+            )?;
+            writeln!(f, "  verdict: {}", comparison.verdict())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Comparison {
+    fn verdict(&self) -> String {
+        match self.adjusted_p_value {
+            Some(p_value) if p_value < SIGNIFICANCE_LEVEL => {
+                let better = if self.vargha_delaney_a12 > 0.5 {
+                    &self.first
+                } else {
+                    &self.second
+                };
+                format!("{} is better (alpha = {})", better, SIGNIFICANCE_LEVEL)
+            }
+            _ => format!("no significant difference (alpha = {})", SIGNIFICANCE_LEVEL),
+        }
+    }
+}
+
+pub fn mann_whitney_u(first: &[f64], second: &[f64]) -> Option<RankTest> {
+    let n1 = first.len() as f64;
+    let n2 = second.len() as f64;
+    let n = n1 + n2;
+
+    let combined: Vec<f64> = first.iter().chain(second).copied().collect();
+    let (ranks, tie_correction) = rank(&combined);
+
+    let first_rank_sum: f64 = ranks.iter().take(first.len()).sum();
+    let u = first_rank_sum - n1 * (n1 + 1.0) / 2.0;
+
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt() * (u - mean).signum();
+
+    Some(RankTest {
+        statistic: u,
+        z,
+        p_value: two_sided_p_value(z),
+    })
+}
+
+pub fn wilcoxon_signed_rank(first: &[f64], second: &[f64]) -> Option<RankTest> {
+    if first.len() != second.len() {
+        return None;
+    }
+
+    let differences: Vec<f64> = first
+        .iter()
+        .zip(second)
+        .map(|(a, b)| a - b)
+        .filter(|difference| *difference != 0.0)
+        .collect();
+    if differences.is_empty() {
+        return None;
+    }
+
+    let n = differences.len() as f64;
+    let absolute: Vec<f64> = differences.iter().map(|d| d.abs()).collect();
+    let (ranks, tie_correction) = rank(&absolute);
+
+    let positive_rank_sum: f64 = ranks
+        .iter()
+        .zip(&differences)
+        .filter(|(_, difference)| **difference > 0.0)
+        .fold(0.0, |sum, (rank, _)| sum + rank);
+
+    let mean = n * (n + 1.0) / 4.0;
+    let variance = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_correction / 48.0;
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let z = ((positive_rank_sum - mean).abs() - 0.5).max(0.0) / variance.sqrt()
+        * (positive_rank_sum - mean).signum();
+
+    Some(RankTest {
+        statistic: positive_rank_sum,
+        z,
+        p_value: two_sided_p_value(z),
+    })
+}
+
+/// probability that a value from first is lower than a value from second, ties counting as half
+pub fn vargha_delaney_a12(first: &[f64], second: &[f64]) -> f64 {
+    let wins: f64 = first
+        .iter()
+        .flat_map(|a| second.iter().map(move |b| (a, b)))
+        .map(|(a, b)| match a.partial_cmp(b).unwrap_or(Equal) {
+            std::cmp::Ordering::Less => 1.0,
+            Equal => 0.5,
+            std::cmp::Ordering::Greater => 0.0,
+        })
+        .sum();
+
+    wins / (first.len() * second.len()) as f64
+}
+
+pub fn cohens_d(first: &[f64], second: &[f64]) -> f64 {
+    let n1 = first.len() as f64;
+    let n2 = second.len() as f64;
+    let pooled_variance = ((n1 - 1.0) * sample_deviation(first).powi(2)
+        + (n2 - 1.0) * sample_deviation(second).powi(2))
+        / (n1 + n2 - 2.0);
+
+    if pooled_variance == 0.0 {
+        return 0.0;
+    }
+
+    (mean(first) - mean(second)) / pooled_variance.sqrt()
+}
+
+// sorts the p-values ascending and multiplies them by the number of hypotheses still remaining
+fn adjust_with_holm(comparisons: &mut [Comparison]) {
+    let mut tested: Vec<(usize, f64)> = comparisons
+        .iter()
+        .enumerate()
+        .filter_map(|(i, comparison)| comparison.mann_whitney.as_ref().map(|t| (i, t.p_value)))
+        .collect();
+    tested.sort_by(|first, second| first.1.partial_cmp(&second.1).unwrap_or(Equal));
+
+    let hypotheses = tested.len();
+    let mut running_max: f64 = 0.0;
+    for (order, (i, p_value)) in tested.into_iter().enumerate() {
+        running_max = running_max.max((p_value * (hypotheses - order) as f64).min(1.0));
+        comparisons[i].adjusted_p_value = Some(running_max);
+    }
+}
+
+// returns average ranks (1-based) and the tie correction term sum(t^3 - t)
+fn rank(values: &[f64]) -> (Vec<f64>, f64) {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&first, &second| values[first].partial_cmp(&values[second]).unwrap_or(Equal));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut tie_correction = 0.0;
+
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+
+        let average_rank = (start + end) as f64 / 2.0 + 1.0;
+        for &index in &order[start..=end] {
+            ranks[index] = average_rank;
+        }
+
+        let tied = (end - start + 1) as f64;
+        tie_correction += tied * tied * tied - tied;
+        start = end + 1;
+    }
+
+    (ranks, tie_correction)
+}
+
+fn bootstrap_confidence_interval<F>(rng: &mut StdRng, mut statistic: F) -> (f64, f64)
+where
+    F: FnMut(&mut StdRng) -> f64,
+{
+    let mut estimates: Vec<f64> = (0..BOOTSTRAP_RESAMPLES).map(|_| statistic(rng)).collect();
+    estimates.sort_by(|first, second| first.partial_cmp(second).unwrap_or(Equal));
+
+    let lower = ((BOOTSTRAP_RESAMPLES as f64) * 0.025) as usize;
+    let upper = ((BOOTSTRAP_RESAMPLES as f64) * 0.975) as usize;
+
+    (
+        estimates[lower],
+        estimates[upper.min(BOOTSTRAP_RESAMPLES - 1)],
+    )
+}
+
+fn resample(values: &[f64], rng: &mut StdRng) -> Vec<f64> {
+    (0..values.len())
+        .map(|_| values[rng.gen_range(0..values.len())])
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|first, second| first.partial_cmp(second).unwrap_or(Equal));
+
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+fn sample_deviation(values: &[f64]) -> f64 {
+    let average = mean(values);
+    (values
+        .iter()
+        .map(|value| (value - average).powi(2))
+        .sum::<f64>()
+        / (values.len() as f64 - 1.0))
+        .sqrt()
+}
+
+fn two_sided_p_value(z: f64) -> f64 {
+    erfc(z.abs() / std::f64::consts::SQRT_2).min(1.0)
+}
+
+// complementary error function, Numerical Recipes' Chebyshev approximation (error < 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let tau = t
+        * (-x * x - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98
+                                    + t * (1.488_515_87
+                                        + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+
+    if x >= 0.0 {
+        tau
+    } else {
+        2.0 - tau
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_log::GenerationRecord;
+
+    const FIRST: [f64; 5] = [1.0, 2.0, 3.0, 4.0, 5.0];
+    const SECOND: [f64; 5] = [6.0, 7.0, 8.0, 9.0, 10.0];
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn describes_samples() {
+        assert_close(mean(&FIRST), 3.0);
+        assert_close(median(&FIRST), 3.0);
+        assert_close(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_close(sample_deviation(&FIRST), 2.5f64.sqrt());
+    }
+
+    #[test]
+    fn ranks_ties_by_their_average() {
+        let (ranks, tie_correction) = rank(&[3.0, 1.0, 2.0, 2.0]);
+
+        assert_eq!(ranks, vec![4.0, 1.0, 2.5, 2.5]);
+        assert_close(tie_correction, 6.0);
+    }
+
+    #[test]
+    fn tests_separated_samples_with_mann_whitney() {
+        let test = mann_whitney_u(&FIRST, &SECOND).unwrap();
+
+        assert_close(test.statistic, 0.0);
+        assert_close(test.z, -2.506718);
+        assert_close(test.p_value, 0.012186);
+    }
+
+    #[test]
+    fn tests_tied_samples_with_mann_whitney() {
+        let test = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0, 5.0], &[2.0, 4.0, 5.0, 6.0, 7.0]).unwrap();
+
+        assert_close(test.statistic, 4.5);
+        assert_close(test.z, -1.590990);
+        assert_close(test.p_value, 0.111612);
+        assert!(mann_whitney_u(&[1.0, 1.0], &[1.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn tests_paired_samples_with_wilcoxon() {
+        let test = wilcoxon_signed_rank(&FIRST, &[2.0, 4.0, 6.0, 8.0, 10.0]).unwrap();
+
+        assert_close(test.statistic, 0.0);
+        assert_close(test.z, -1.887760);
+        assert_close(test.p_value, 0.059058);
+        assert!(wilcoxon_signed_rank(&FIRST, &FIRST).is_none());
+        assert!(wilcoxon_signed_rank(&FIRST, &SECOND[1..]).is_none());
+    }
+
+    #[test]
+    fn measures_effect_sizes() {
+        assert_close(vargha_delaney_a12(&FIRST, &SECOND), 1.0);
+        assert_close(vargha_delaney_a12(&SECOND, &FIRST), 0.0);
+        assert_close(vargha_delaney_a12(&FIRST, &FIRST), 0.5);
+        assert_close(cohens_d(&FIRST, &SECOND), -10f64.sqrt());
+    }
+
+    #[test]
+    fn adjusts_p_values_with_holm() {
+        let mut comparisons: Vec<Comparison> = [0.01, 0.04, 0.03]
+            .iter()
+            .map(|&p_value| Comparison {
+                first: String::new(),
+                second: String::new(),
+                mann_whitney: Some(RankTest {
+                    statistic: 0.0,
+                    z: 0.0,
+                    p_value,
+                }),
+                adjusted_p_value: None,
+                wilcoxon: None,
+                vargha_delaney_a12: 0.5,
+                cliffs_delta: 0.0,
+                cohens_d: 0.0,
+                mean_difference: 0.0,
+                mean_difference_confidence_interval: (0.0, 0.0),
+            })
+            .collect();
+
+        adjust_with_holm(&mut comparisons);
+
+        let adjusted: Vec<f64> = comparisons
+            .iter()
+            .map(|comparison| comparison.adjusted_p_value.unwrap())
+            .collect();
+        // 0.03 * 2 = 0.06 carries over to the larger 0.04 * 1
+        assert_close(adjusted[0], 0.03);
+        assert_close(adjusted[1], 0.06);
+        assert_close(adjusted[2], 0.06);
+    }
+
+    fn run_log(name: &str, final_best_fitnesses: &[f64]) -> RunLog {
+        RunLog {
+            name: name.to_string(),
+            runs: final_best_fitnesses
+                .iter()
+                .map(|&best| {
+                    vec![GenerationRecord {
+                        best,
+                        worst: best,
+                        average: best,
+                        deviation: 0.0,
+                    }]
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_too_few_configurations_and_runs() {
+        assert!(matches!(
+            ComparisonReport::new(&[run_log("a", &FIRST)], false),
+            Err(ConfigError::TooFewConfigurations { configurations: 1 })
+        ));
+        assert!(matches!(
+            ComparisonReport::new(&[run_log("a", &FIRST), run_log("b", &[1.0])], false),
+            Err(ConfigError::TooFewRuns { ref configuration, runs: 1 }) if configuration == "b"
+        ));
+        assert!(
+            ComparisonReport::new(&[run_log("a", &FIRST), run_log("b", &SECOND)], true).is_ok()
+        );
+    }
+}